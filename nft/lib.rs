@@ -2,25 +2,22 @@
 
 #[ink::contract(env = MyEnvironment)]
 pub mod erc721 {
-    #[derive(Clone)]
-    pub struct MyEnvironment;
-
-    impl ink::env::Environment for MyEnvironment {
-        const MAX_EVENT_TOPICS: usize = 3;
-        type AccountId = [u8; 20];
-        type Balance = u128;
-        type Hash = [u8; 32];
-        type Timestamp = u64;
-        type BlockNumber = u32;
-        type ChainExtension = ::ink::env::NoChainExtension;
-    }
+    pub type MyEnvironment = vns_common::VnsEnvironment;
 
-    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::codegen::TraitCallBuilder;
+    use ink::contract_ref;
+    use ink::env::call::FromAccountId;
     use ink::prelude::string::String;
-    use ink::storage::Mapping;
+    use ink::storage::{Lazy, Mapping};
+    use vns_common::access::{
+        AccessControl, AdminTransferProposed, AdminTransferred, Role, RoleGranted, RoleRevoked,
+        ADMIN, MANAGER, PAUSER,
+    };
+    use vns_common::call::{self, CallError, CallLimits, CallLimitsSet};
+    use vns_common::nft::{NftError, VnsNft};
+    use vns_common::resolver::VnsResolver;
 
-    /// A token ID.
-    pub type TokenId = u64;
+    pub use vns_common::nft::TokenId;
 
     #[ink(storage)]
     pub struct Erc721 {
@@ -36,20 +33,15 @@ pub mod erc721 {
         token_uri: Mapping<TokenId, String>,
         /// Importing Resolver contract address
         resolver_contract_address: AccountId,
+        /// Admin and role members, e.g. the pausers.
+        access: AccessControl,
+        /// Whether state changing messages are halted.
+        paused: bool,
+        /// Limits of the calls to the resolver.
+        call_limits: Lazy<CallLimits>,
     }
 
-    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    pub enum Error {
-        NotOwner,
-        NotApproved,
-        TokenExists,
-        TokenNotFound,
-        CannotInsert,
-        CannotFetchValue,
-        NotAllowed,
-        DomainNotRegistered,
-    }
+    pub type Error = NftError;
 
     /// Event emitted when a token transfer occurs.
     #[ink(event)]
@@ -82,8 +74,26 @@ pub mod erc721 {
         approved: bool,
     }
 
+    /// Event emitted when the contract is paused.
+    #[ink(event)]
+    pub struct Paused {
+        account: AccountId,
+    }
+
+    /// Event emitted when the contract is unpaused.
+    #[ink(event)]
+    pub struct Unpaused {
+        account: AccountId,
+    }
+
+    /// Event emitted when the code of the contract is replaced.
+    #[ink(event)]
+    pub struct Upgraded {
+        code_hash: Hash,
+    }
+
     impl Erc721 {
-        /// Creates a new ERC-721 token contract.
+        /// Creates a new ERC-721 token contract. The deployer becomes the admin and a pauser.
         #[ink(constructor)]
         pub fn new(resolver_contract_address: AccountId) -> Self {
             Self {
@@ -93,6 +103,9 @@ pub mod erc721 {
                 operator_approvals: Mapping::default(),
                 resolver_contract_address,
                 token_uri: Mapping::default(),
+                access: AccessControl::new(Self::env().caller(), &[PAUSER]),
+                paused: false,
+                call_limits: Lazy::default(),
             }
         }
 
@@ -124,6 +137,7 @@ pub mod erc721 {
         /// Approves or disapproves the operator for all tokens of the caller.
         #[ink(message)]
         pub fn set_approval_for_all(&mut self, to: AccountId, approved: bool) -> Result<(), Error> {
+            self.ensure_not_paused()?;
             self.approve_for_all(to, approved)?;
             Ok(())
         }
@@ -131,6 +145,7 @@ pub mod erc721 {
         /// Approves the account to transfer the specified token on behalf of the caller.
         #[ink(message)]
         pub fn approve(&mut self, to: AccountId, id: TokenId) -> Result<(), Error> {
+            self.ensure_not_paused()?;
             self.approve_for(&to, id)?;
             Ok(())
         }
@@ -138,6 +153,7 @@ pub mod erc721 {
         /// Transfers the token from the caller to the given destination.
         #[ink(message)]
         pub fn transfer(&mut self, destination: AccountId, id: TokenId) -> Result<(), Error> {
+            self.ensure_not_paused()?;
             let caller = self.env().caller();
             self.transfer_token_from(&caller, &destination, id)?;
             Ok(())
@@ -151,48 +167,15 @@ pub mod erc721 {
             to: AccountId,
             id: TokenId,
         ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
             self.transfer_token_from(&from, &to, id)?;
             Ok(())
         }
 
-        /// Creates a new token.
-        #[ink(message)]
-        pub fn mint(
-            &mut self,
-            id: TokenId,
-            domain_name: String,
-            caller: AccountId,
-            token_uri: String,
-        ) -> Result<(), Error> {
-            let domain_availaibilty = build_call::<MyEnvironment>()
-                .call(AccountId::from(self.resolver_contract_address))
-                .call_v1()
-                .gas_limit(0)
-                .transferred_value(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
-                        "check_domain_availablility"
-                    )))
-                    .push_arg(domain_name),
-                )
-                .returns::<bool>()
-                .invoke();
-
-            assert!(!domain_availaibilty, "domain is not registered");
-
-            self.token_uri.insert(id, &token_uri);
-            self.add_token_to(&caller, id)?;
-            self.env().emit_event(Transfer {
-                from: Some(AccountId::from([0x0; 20])),
-                to: Some(caller),
-                id,
-            });
-            Ok(())
-        }
-
         /// Deletes an existing token. Only the owner can burn the token.
         #[ink(message)]
         pub fn burn(&mut self, id: TokenId) -> Result<(), Error> {
+            self.ensure_not_paused()?;
             let caller = self.env().caller();
             let Self {
                 token_owner,
@@ -222,6 +205,158 @@ pub mod erc721 {
             Ok(())
         }
 
+        /// Halts approvals, transfers, mints and burns until `unpause`.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<(), Error> {
+            self.ensure_role(PAUSER)?;
+            self.paused = true;
+            self.env().emit_event(Paused {
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Resumes approvals, transfers, mints and burns.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<(), Error> {
+            self.ensure_role(PAUSER)?;
+            self.paused = false;
+            self.env().emit_event(Unpaused {
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Returns `true` if the contract is paused.
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Replaces the code of the contract, keeping its storage. Only the admin can upgrade.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+            self.ensure_role(ADMIN)?;
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+            self.env().emit_event(Upgraded { code_hash });
+            Ok(())
+        }
+
+        /// Sets the limits of the calls to the resolver. Only the admin can set them.
+        #[ink(message)]
+        pub fn set_call_limits(&mut self, call_limits: CallLimits) -> Result<(), Error> {
+            self.ensure_role(ADMIN)?;
+            self.call_limits.set(&call_limits);
+            self.env().emit_event(CallLimitsSet {
+                ref_time_limit: call_limits.ref_time_limit,
+                proof_size_limit: call_limits.proof_size_limit,
+                storage_deposit_limit: call_limits.storage_deposit_limit,
+            });
+            Ok(())
+        }
+
+        /// Returns the limits of the calls to the resolver, zero meaning no limit.
+        #[ink(message)]
+        pub fn read_call_limits(&self) -> CallLimits {
+            self.call_limits.get().unwrap_or_default()
+        }
+
+        /// Returns the admin.
+        #[ink(message)]
+        pub fn admin(&self) -> AccountId {
+            self.access.admin()
+        }
+
+        /// Returns the proposed next admin, if any.
+        #[ink(message)]
+        pub fn pending_admin(&self) -> Option<AccountId> {
+            self.access.pending_admin()
+        }
+
+        /// Returns `true` if `account` has `role`.
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+            self.access.has_role(role, account)
+        }
+
+        /// Grants `role` to `account`. Only the admin can grant roles.
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<(), Error> {
+            let sender = self.env().caller();
+            self.access.grant_role(sender, role, account)?;
+            self.env().emit_event(RoleGranted {
+                account,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Revokes `role` from `account`. Only the admin can revoke roles.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<(), Error> {
+            let sender = self.env().caller();
+            self.access.revoke_role(sender, role, account)?;
+            self.env().emit_event(RoleRevoked {
+                account,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Gives up `role` of the caller.
+        #[ink(message)]
+        pub fn renounce_role(&mut self, role: Role) -> Result<(), Error> {
+            let sender = self.env().caller();
+            self.access.renounce_role(sender, role)?;
+            self.env().emit_event(RoleRevoked {
+                account: sender,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Proposes the next admin, who takes over by calling `accept_admin`.
+        #[ink(message)]
+        pub fn propose_admin(&mut self, pending_admin: AccountId) -> Result<(), Error> {
+            self.access
+                .propose_admin(self.env().caller(), pending_admin)?;
+            self.env().emit_event(AdminTransferProposed {
+                admin: self.access.admin(),
+                pending_admin,
+            });
+            Ok(())
+        }
+
+        /// Makes the caller, who has to be the proposed admin, the admin.
+        #[ink(message)]
+        pub fn accept_admin(&mut self) -> Result<(), Error> {
+            let admin = self.env().caller();
+            let previous_admin = self.access.accept_admin(admin)?;
+            self.env().emit_event(AdminTransferred {
+                previous_admin,
+                admin,
+            });
+            Ok(())
+        }
+
+        /// Fails unless the caller has `role`.
+        fn ensure_role(&self, role: Role) -> Result<(), Error> {
+            Ok(self.access.ensure_role(role, self.env().caller())?)
+        }
+
+        /// Fails with `Paused` while the contract is paused.
+        fn ensure_not_paused(&self) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
         /// Transfers token `id` `from` the sender to the `to` `AccountId`.
         fn transfer_token_from(
             &mut self,
@@ -371,16 +506,104 @@ pub mod erc721 {
         }
     }
 
+    impl VnsNft for Erc721 {
+        /// Creates a new token. Only the registrar, which holds the `MANAGER` role,
+        /// may mint.
+        #[ink(message)]
+        fn mint(
+            &mut self,
+            id: TokenId,
+            domain_name: String,
+            caller: AccountId,
+            token_uri: String,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            self.ensure_role(MANAGER)?;
+            let resolver: contract_ref!(VnsResolver, MyEnvironment) =
+                FromAccountId::from_account_id(self.resolver_contract_address);
+            let domain_availaibilty = call::invoke(
+                resolver.call().check_domain_availablility(domain_name),
+                self.read_call_limits(),
+            );
+            ensure_registered(domain_availaibilty)?;
+
+            self.token_uri.insert(id, &token_uri);
+            self.add_token_to(&caller, id)?;
+            self.env().emit_event(Transfer {
+                from: Some(AccountId::from([0x0; 20])),
+                to: Some(caller),
+                id,
+            });
+            Ok(())
+        }
+    }
+
+    /// Maps the resolver's answer to whether a domain is available into an error
+    /// unless the domain is registered.
+    fn ensure_registered(availability: core::result::Result<bool, CallError>) -> Result<(), Error> {
+        if availability? {
+            return Err(Error::DomainNotRegistered);
+        }
+        Ok(())
+    }
+
     /// Unit tests
     #[cfg(test)]
     mod tests {
         /// Imports all the definitions from the outer scope so we can use them here.
         use super::*;
+        use vns_common::access::AccessError;
+        use vns_common::testing::{answer, mock_callee, set_caller, trap};
+
+        const RESOLVER: AccountId = [0x9; 20];
+
+        /// Creates the NFT with a mocked resolver on which every domain is registered.
+        fn new_erc721() -> Erc721 {
+            mock_callee(RESOLVER, |selector, _| match selector {
+                ink::selector_bytes!("check_domain_availablility") => answer(false),
+                _ => trap(),
+            });
+            let mut erc721 = Erc721::new(RESOLVER);
+            // the default caller mints in place of the registrar, granted without an
+            // event so the tests count only their own events
+            let admin = erc721.admin();
+            erc721.access.grant_role(admin, MANAGER, admin).unwrap();
+            erc721
+        }
+
+        #[ink::test]
+        fn mint_needs_registered_domain() {
+            let mut erc721 = new_erc721();
+            mock_callee(RESOLVER, |_, _| answer(true));
+            assert_eq!(
+                erc721.mint(1, "arpit.vne".to_string(), [0x1; 20], "arpit".to_string()),
+                Err(Error::DomainNotRegistered)
+            );
+
+            // a resolver that fails is reported rather than trapping the mint
+            mock_callee(RESOLVER, |_, _| trap());
+            assert_eq!(
+                erc721.mint(1, "arpit.vne".to_string(), [0x1; 20], "arpit".to_string()),
+                Err(Error::Call(CallError::Trapped))
+            );
+            assert_eq!(erc721.owner_of(1), None);
+        }
+
+        #[ink::test]
+        fn mint_requires_manager() {
+            let mut erc721 = new_erc721();
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                erc721.mint(1, "arpit.vne".to_string(), [0x2; 20], "arpit".to_string()),
+                Err(Error::Access(AccessError::MissingRole { role: MANAGER }))
+            );
+            assert_eq!(erc721.owner_of(1), None);
+        }
 
         #[ink::test]
         fn mint_works() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x1; 20]));
+            let mut erc721 = new_erc721();
             // Token 1 does not exists.
             assert_eq!(erc721.owner_of(1), None);
             // Alice does not owns tokens.
@@ -402,7 +625,7 @@ pub mod erc721 {
         #[ink::test]
         fn mint_existing_should_fail() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1.
             assert_eq!(
                 erc721.mint(
@@ -435,7 +658,7 @@ pub mod erc721 {
         #[ink::test]
         fn transfer_works() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1 for Alice
             assert_eq!(
                 erc721.mint(
@@ -463,7 +686,7 @@ pub mod erc721 {
         #[ink::test]
         fn invalid_transfer_should_fail() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Transfer token fails if it does not exists.
             assert_eq!(
                 erc721.transfer(AccountId::from([0x2; 20]), 1),
@@ -486,6 +709,7 @@ pub mod erc721 {
             // Token Id 2 is owned by Alice.
             assert_eq!(erc721.owner_of(2), Some(AccountId::from([0x1; 20])));
             // Set Bob as caller
+            set_caller(AccountId::from([0x2; 20]));
             // Bob cannot transfer not owned tokens.
             assert_eq!(
                 erc721.transfer(AccountId::from([0x3; 20]), 2),
@@ -496,7 +720,7 @@ pub mod erc721 {
         #[ink::test]
         fn approved_transfer_works() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1.
             assert_eq!(
                 erc721.mint(
@@ -512,6 +736,7 @@ pub mod erc721 {
             // Approve token Id 1 transfer for Bob on behalf of Alice.
             assert_eq!(erc721.approve(AccountId::from([0x2; 20]), 1), Ok(()));
             // Set Bob as caller
+            set_caller(AccountId::from([0x2; 20]));
             // Bob transfers token Id 1 from Alice to Eve.
             assert_eq!(
                 erc721.transfer_from(AccountId::from([0x1; 20]), AccountId::from([0x3; 20]), 1),
@@ -530,7 +755,7 @@ pub mod erc721 {
         #[ink::test]
         fn approved_for_all_works() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1.
             assert_eq!(
                 erc721.mint(
@@ -563,6 +788,7 @@ pub mod erc721 {
                 erc721.is_approved_for_all(AccountId::from([0x1; 20]), AccountId::from([0x2; 20]))
             );
             // Set Bob as caller
+            set_caller(AccountId::from([0x2; 20]));
             // Bob transfers token Id 1 from Alice to Eve.
             assert_eq!(
                 erc721.transfer_from(AccountId::from([0x1; 20]), AccountId::from([0x3; 20]), 1),
@@ -581,6 +807,8 @@ pub mod erc721 {
             assert_eq!(erc721.balance_of(AccountId::from([0x2; 20])), 0);
             // Eve owns 2 tokens.
             assert_eq!(erc721.balance_of(AccountId::from([0x3; 20])), 2);
+            // Set Alice as caller
+            set_caller(AccountId::from([0x1; 20]));
             // Remove operator approval for Bob on behalf of Alice.
             assert_eq!(
                 erc721.set_approval_for_all(AccountId::from([0x2; 20]), false),
@@ -595,7 +823,7 @@ pub mod erc721 {
         #[ink::test]
         fn approve_nonexistent_token_should_fail() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Approve transfer of nonexistent token id 1
             assert_eq!(
                 erc721.approve(AccountId::from([0x2; 20]), 1),
@@ -606,7 +834,7 @@ pub mod erc721 {
         #[ink::test]
         fn not_approved_transfer_should_fail() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1.
             assert_eq!(
                 erc721.mint(
@@ -624,6 +852,7 @@ pub mod erc721 {
             // Eve does not owns tokens.
             assert_eq!(erc721.balance_of(AccountId::from([0x3; 20])), 0);
             // Set Eve as caller
+            set_caller(AccountId::from([0x3; 20]));
             // Eve is not an approved operator by Alice.
             assert_eq!(
                 erc721.transfer_from(AccountId::from([0x1; 20]), AccountId::from([0x4; 20]), 1),
@@ -640,7 +869,7 @@ pub mod erc721 {
         #[ink::test]
         fn burn_works() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1 for Alice
             assert_eq!(
                 erc721.mint(
@@ -666,15 +895,83 @@ pub mod erc721 {
         #[ink::test]
         fn burn_fails_token_not_found() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Try burning a non existent token
             assert_eq!(erc721.burn(1), Err(Error::TokenNotFound));
         }
 
+        #[ink::test]
+        fn pause_works() {
+            // Create a new contract instance, the deployer is a pauser.
+            let mut erc721 = new_erc721();
+            assert!(!erc721.is_paused());
+            // State changes fail while paused.
+            assert_eq!(erc721.pause(), Ok(()));
+            assert!(erc721.is_paused());
+            assert_eq!(
+                erc721.transfer(AccountId::from([0x2; 20]), 1),
+                Err(Error::Paused)
+            );
+            assert_eq!(erc721.burn(1), Err(Error::Paused));
+            // Reads keep working.
+            assert_eq!(erc721.owner_of(1), None);
+            // State changes work again once unpaused.
+            assert_eq!(erc721.unpause(), Ok(()));
+            assert_eq!(erc721.burn(1), Err(Error::TokenNotFound));
+        }
+
+        #[ink::test]
+        fn upgrade_requires_admin() {
+            // Create a new contract instance, the deployer is the admin.
+            let mut erc721 = new_erc721();
+            // Only the admin can upgrade.
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                erc721.upgrade([0x5; 32]),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+
+        #[ink::test]
+        fn set_call_limits_works() {
+            let mut erc721 = new_erc721();
+            // No limits by default.
+            assert_eq!(erc721.read_call_limits(), CallLimits::default());
+            let limits = CallLimits {
+                ref_time_limit: 5_000_000_000,
+                proof_size_limit: 1_000_000,
+                storage_deposit_limit: 0,
+            };
+            assert_eq!(erc721.set_call_limits(limits), Ok(()));
+            assert_eq!(erc721.read_call_limits(), limits);
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+            // Only the admin can set them.
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                erc721.set_call_limits(CallLimits::default()),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+
+        #[ink::test]
+        fn failed_resolver_calls_are_errors() {
+            assert_eq!(ensure_registered(Ok(false)), Ok(()));
+            assert_eq!(ensure_registered(Ok(true)), Err(Error::DomainNotRegistered));
+            // The resolver trapped or does not have the message.
+            assert_eq!(
+                ensure_registered(Err(CallError::Trapped)),
+                Err(Error::Call(CallError::Trapped))
+            );
+            assert_eq!(
+                ensure_registered(Err(CallError::UnknownMessage)),
+                Err(Error::Call(CallError::UnknownMessage))
+            );
+        }
+
         #[ink::test]
         fn burn_fails_not_owner() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1 for Alice
             assert_eq!(
                 erc721.mint(
@@ -686,13 +983,14 @@ pub mod erc721 {
                 Ok(())
             );
             // Try burning this token with a different account
+            set_caller(AccountId::from([0x2; 20]));
             assert_eq!(erc721.burn(1), Err(Error::NotOwner));
         }
 
-        #[ink::test]    
+        #[ink::test]
         fn transfer_from_fails_not_owner() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1 for Alice
             assert_eq!(
                 erc721.mint(
@@ -720,6 +1018,7 @@ pub mod erc721 {
                 Ok(())
             );
             // Set caller to Bob
+            set_caller(AccountId::from([0x2; 20]));
             // Bob makes invalid call to transfer_from (Alice is token owner, not Frank)
             assert_eq!(
                 erc721.transfer_from(AccountId::from([0x4; 20]), AccountId::from([0x2; 20]), 1),
@@ -730,7 +1029,7 @@ pub mod erc721 {
         #[ink::test]
         fn transfer_fails_not_owner() {
             // Create a new contract instance.
-            let mut erc721 = Erc721::new(AccountId::from([0x0; 20]));
+            let mut erc721 = new_erc721();
             // Create token Id 1 for Alice
             assert_eq!(
                 erc721.mint(
@@ -747,13 +1046,12 @@ pub mod erc721 {
                 Ok(())
             );
             // Set caller to bob
-
+            set_caller(AccountId::from([0x2; 20]));
             // Bob makes invalid call to transfer (he is not token owner, Alice is)
             assert_eq!(
                 erc721.transfer(AccountId::from([0x2; 20]), 1),
                 Err(Error::NotOwner)
             );
         }
-
     }
 }
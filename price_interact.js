@@ -41,6 +41,24 @@ async function main() {
         }
     }
 
+    async function quote(name, duration) {
+        const { result, gasConsumed, output } = await contract.query["vnsPriceOracle::quote"](
+            userKeyring.address,
+            { gasLimit: gasLimit, storageDepositLimit: null },
+            name,
+            duration
+        );
+        console.log(gasConsumed.toHuman(), "gas used");
+        if (result.isOk) {
+            const priceQuote = output.toHuman()
+            console.log("price quote is : ".yellow, priceQuote.Ok);
+            return priceQuote;
+        } else {
+            console.error('Failed to quote price:', output);
+            return null;
+        }
+    }
+
     async function read_owner() {
         const { result, gasUsed, output } = await contract.query["readOwner"](
             userKeyring.address,
@@ -153,10 +171,25 @@ async function main() {
             });
     }
 
+    // native balance worth one USD
+    async function report_exchange_rate(native_per_usd) {
+        await contract.tx
+            .reportExchangeRate({ storageDepositLimit, gasLimit }, native_per_usd)
+            .signAndSend(userKeyring, result => {
+                if (result.status.isInBlock) {
+                    console.log(`initialised in block : ${result.status.asInBlock}`.cyan);
+                } else if (result.status.isFinalized) {
+                    console.log(`finalized in block : ${result.status.asFinalized}`.cyan);
+                }
+            });
+    }
+
+    // await report_exchange_rate(1000000000000000000n);
     await calculate_price("akt.vne", 300000);
+    // await quote("akt.vne", 300000);
     // await read_owner();
-    // await set_price_per_letter(20000000000000);
-    // await set_price_per_year(20000000000000);
+    // await set_price_per_letter(100);
+    // await set_price_per_year(2000);
     // await add_premium_name("antiers.vne");
     // await read_premium_names();
     // await remove_premium_name("antiers.vne");
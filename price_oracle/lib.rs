@@ -2,76 +2,286 @@
 
 #[ink::contract(env = MyEnvironment)]
 pub mod priceoracle {
-    #[derive(Clone)]
-    pub struct MyEnvironment;
+    pub type MyEnvironment = vns_common::VnsEnvironment;
 
-    impl ink::env::Environment for MyEnvironment {
-        const MAX_EVENT_TOPICS: usize = 3;
-        type AccountId = [u8; 20];
-        type Balance = u128;
-        type Hash = [u8; 32];
-        type Timestamp = u64;
-        type BlockNumber = u32;
-        type ChainExtension = ::ink::env::NoChainExtension;
-    }
+    use ink::storage::{Lazy, Mapping};
+    use vns_common::access::{
+        AccessControl, AdminTransferProposed, AdminTransferred, Role, RoleGranted, RoleRevoked,
+        ADMIN, MANAGER, PRICE_SETTER, RATE_REPORTER,
+    };
+    use vns_common::name;
+    use vns_common::price::{Price, PriceError, PriceOracleError, PriceQuote, VnsPriceOracle};
 
-    /// Define the Priceoracle contract.
+    /// Define the Priceoracle contract. All prices are kept in USD cents and converted
+    /// to the native balance with the last reported exchange rate.
     #[ink(storage)]
     pub struct Priceoracle {
-        owner: AccountId,
+        access: AccessControl,
         price_per_letter: Balance,
         price_per_year: Balance,
         premium_names: ink::prelude::vec::Vec<ink::prelude::string::String>,
+        /// Version 1 pricing in the native balance, moved to `usd_tld_pricing` by `migrate`.
+        tld_pricing: Mapping<ink::prelude::string::String, TldPricing>,
+        release_premium: Option<ReleasePremium>,
+        release_times: Mapping<ink::prelude::string::String, Timestamp>,
+        storage_version: u32,
+        token_rates: Mapping<AccountId, ReportedTokenRate>,
+        exchange_rate: Lazy<ExchangeRate>,
+        max_rate_age: Lazy<Timestamp>,
+        usd_tld_pricing: Mapping<ink::prelude::string::String, TldPricing>,
+        /// Exchange rate `migrate` converts with, so every call uses the same one.
+        migration_rate: Lazy<Balance>,
+    }
+
+    /// Pricing parameters of a single TLD, used instead of the default ones.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct TldPricing {
+        pub price_per_letter: Balance,
+        pub price_per_year: Balance,
+    }
+
+    /// Premium charged on top of the price of a recently released name. It starts at
+    /// `start_premium` when the name is released and decays to zero over `duration`.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct ReleasePremium {
+        pub start_premium: Balance,
+        pub duration: Timestamp,
+        pub decay: PremiumDecay,
+    }
+
+    /// Exchange rate between USD and the native balance, as last reported.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct ExchangeRate {
+        /// Native balance worth one USD.
+        pub native_per_usd: Balance,
+        pub updated_at: Timestamp,
+    }
+
+    /// Exchange rate of a PSP22 token: `tokens` of it are worth `native` of the native balance.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct TokenRate {
+        pub tokens: Balance,
+        pub native: Balance,
+    }
+
+    /// A token rate as last reported. It goes stale like the exchange rate.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct ReportedTokenRate {
+        pub rate: TokenRate,
+        pub updated_at: Timestamp,
+    }
+
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum PremiumDecay {
+        Linear,
+        /// Halves every `half_life`, shifted down so it still ends at zero.
+        Exponential {
+            half_life: Timestamp,
+        },
+    }
+
+    pub type Error = PriceOracleError;
+
+    /// Event emitted when a reporter pushes a new exchange rate.
+    #[ink(event)]
+    pub struct ExchangeRateReported {
+        native_per_usd: Balance,
+        #[ink(topic)]
+        reporter: AccountId,
+    }
+
+    /// Event emitted when the code of the contract is replaced.
+    #[ink(event)]
+    pub struct Upgraded {
+        code_hash: Hash,
     }
 
+    /// Event emitted when the storage is migrated to a newer layout.
+    #[ink(event)]
+    pub struct Migrated {
+        from_version: u32,
+        to_version: u32,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// Version of the storage layout of this code, see `migrate`.
+    pub const STORAGE_VERSION: u32 = 2;
+
+    /// How old the exchange rate may be until `set_max_rate_age` is called: one hour.
+    pub const DEFAULT_MAX_RATE_AGE: Timestamp = 60 * 60 * 1000;
+
     impl Priceoracle {
         /// Constructor to initialize the owner and default values for `price_per_letter` and `price_per_year`.
+        /// The owner is the admin and also gets the price setter, manager and rate reporter roles.
         #[ink(constructor)]
         pub fn new(owner: AccountId) -> Self {
             // let caller = Self::env().caller();
             Self {
-                owner,
-                price_per_letter: 1u128.saturating_mul(10u128.pow(18)), // 1 Ether equivalent
-                price_per_year: 20u128.saturating_mul(10u128.pow(18)),  // 20 Ether equivalent
+                access: AccessControl::new(owner, &[PRICE_SETTER, MANAGER, RATE_REPORTER]),
+                price_per_letter: 100, // 1 USD
+                price_per_year: 2000,  // 20 USD
                 premium_names: ink::prelude::vec::Vec::new(),
+                tld_pricing: Mapping::default(),
+                release_premium: None,
+                release_times: Mapping::default(),
+                storage_version: STORAGE_VERSION,
+                token_rates: Mapping::default(),
+                exchange_rate: Lazy::default(),
+                max_rate_age: Lazy::default(),
+                usd_tld_pricing: Mapping::default(),
+                migration_rate: Lazy::default(),
             }
         }
 
-        /// Modifier to allow only the contract owner to call certain functions.
-        fn only_owner(&self) {
-            let caller = Self::env().caller();
-            assert_eq!(caller, self.owner, "Not the contract owner");
+        /// Fails unless the caller has `role`.
+        fn ensure_role(&self, role: Role) -> Result<()> {
+            Ok(self.access.ensure_role(role, self.env().caller())?)
         }
 
-        /// Function to update the price per letter (only owner).
+        /// Fails unless the caller has `role` and `migrate` has converted the prices to USD cents.
+        fn ensure_price_setter(&self) -> Result<()> {
+            self.ensure_role(PRICE_SETTER)?;
+            if self.storage_version < STORAGE_VERSION {
+                return Err(Error::Price(PriceError::NotMigrated));
+            }
+            Ok(())
+        }
+
+        /// Function to update the price per letter in USD cents (only price setter).
         #[ink(message)]
-        pub fn set_price_per_letter(&mut self, new_price_per_letter: Balance) {
-            self.only_owner();
+        pub fn set_price_per_letter(&mut self, new_price_per_letter: Balance) -> Result<()> {
+            self.ensure_price_setter()?;
             self.price_per_letter = new_price_per_letter;
+            Ok(())
         }
 
-        /// Function to update the price per year (only owner).
+        /// Function to update the price per year in USD cents (only price setter).
         #[ink(message)]
-        pub fn set_price_per_year(&mut self, new_price_per_year: Balance) {
-            self.only_owner();
+        pub fn set_price_per_year(&mut self, new_price_per_year: Balance) -> Result<()> {
+            self.ensure_price_setter()?;
             self.price_per_year = new_price_per_year;
+            Ok(())
         }
-        /// Function to add a premium name (only owner).
+        /// Function to set the pricing of a TLD (only price setter).
         #[ink(message)]
-        pub fn add_premium_name(&mut self, premium_name: ink::prelude::string::String) {
-            self.only_owner();
+        pub fn set_tld_pricing(
+            &mut self,
+            tld: ink::prelude::string::String,
+            price_per_letter: Balance,
+            price_per_year: Balance,
+        ) -> Result<()> {
+            self.ensure_price_setter()?;
+            let tld = name::fold(&tld);
+            self.tld_pricing.remove(&tld);
+            self.usd_tld_pricing.insert(
+                tld,
+                &TldPricing {
+                    price_per_letter,
+                    price_per_year,
+                },
+            );
+            Ok(())
+        }
+
+        /// Function to remove the pricing of a TLD so it uses the default one again (only price setter).
+        #[ink(message)]
+        pub fn remove_tld_pricing(&mut self, tld: ink::prelude::string::String) -> Result<bool> {
+            self.ensure_role(PRICE_SETTER)?;
+            let tld = name::fold(&tld);
+            let legacy = self.tld_pricing.take(&tld).is_some();
+            Ok(self.usd_tld_pricing.take(&tld).is_some() || legacy)
+        }
+
+        /// Function to get the pricing of a TLD, `None` if it uses the default one.
+        #[ink(message)]
+        pub fn get_tld_pricing(&self, tld: ink::prelude::string::String) -> Option<TldPricing> {
+            self.usd_tld_pricing.get(name::fold(&tld))
+        }
+
+        /// Function to configure the premium of released names, `None` disables it (only price setter).
+        #[ink(message)]
+        pub fn set_release_premium(
+            &mut self,
+            release_premium: Option<ReleasePremium>,
+        ) -> Result<()> {
+            self.ensure_price_setter()?;
+            if let Some(ReleasePremium {
+                decay: PremiumDecay::Exponential { half_life: 0 },
+                ..
+            }) = release_premium
+            {
+                return Err(Error::ZeroHalfLife);
+            }
+            self.release_premium = release_premium;
+            Ok(())
+        }
+
+        /// Function to get the release premium configuration.
+        #[ink(message)]
+        pub fn get_release_premium(&self) -> Option<ReleasePremium> {
+            self.release_premium.clone()
+        }
+
+        /// Function to get when a name was last released.
+        #[ink(message)]
+        pub fn get_release_time(&self, name: ink::prelude::string::String) -> Option<Timestamp> {
+            self.release_times.get(name::fold(&name))
+        }
+
+        /// Function to get the current premium of a released name, in USD cents.
+        #[ink(message)]
+        pub fn calculate_release_premium(&self, name: ink::prelude::string::String) -> Balance {
+            self.release_premium_of(name::fold(&name))
+        }
+
+        /// Function to add a premium name (only price setter).
+        #[ink(message)]
+        pub fn add_premium_name(
+            &mut self,
+            premium_name: ink::prelude::string::String,
+        ) -> Result<()> {
+            self.ensure_role(PRICE_SETTER)?;
             self.premium_names.push(premium_name);
+            Ok(())
         }
 
-        /// Function to remove a premium name (only owner).
+        /// Function to remove a premium name (only price setter).
         #[ink(message)]
-        pub fn remove_premium_name(&mut self, premium_name: ink::prelude::string::String) -> bool {
-            self.only_owner();
+        pub fn remove_premium_name(
+            &mut self,
+            premium_name: ink::prelude::string::String,
+        ) -> Result<bool> {
+            self.ensure_role(PRICE_SETTER)?;
             if let Some(pos) = self.premium_names.iter().position(|x| *x == premium_name) {
                 self.premium_names.swap_remove(pos);
-                true
+                Ok(true)
             } else {
-                false
+                Ok(false)
             }
         }
 
@@ -93,84 +303,910 @@ pub mod priceoracle {
             self.price_per_year
         }
 
-        /// Function to calculate the price.
+        /// Function to calculate the price in the native balance, see `quote`. The premium of
+        /// a recently released name is reported separately from the base price.
         #[ink(message)]
         pub fn calculate_price(
             &self,
             name: ink::prelude::string::String,
             duration: Timestamp,
-        ) -> Option<Balance> {
-            let name_length = name.len() as u128; // Get the number of characters in the name
+        ) -> core::result::Result<Price, PriceError> {
+            let quote = self.quote(name, duration)?;
+            Price::new(quote.base(), quote.premium).ok_or(PriceError::Overflow)
+        }
 
-            assert!(duration > 0, "Duration cannot be zero");
+        /// Function to report the native balance worth one USD (only rate reporter).
+        #[ink(message)]
+        pub fn report_exchange_rate(&mut self, native_per_usd: Balance) -> Result<()> {
+            self.ensure_role(RATE_REPORTER)?;
+            if native_per_usd == 0 {
+                return Err(Error::ZeroRate);
+            }
+            self.exchange_rate.set(&ExchangeRate {
+                native_per_usd,
+                updated_at: self.env().block_timestamp(),
+            });
+            self.env().emit_event(ExchangeRateReported {
+                native_per_usd,
+                reporter: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Function to get the last reported exchange rate.
+        #[ink(message)]
+        pub fn get_exchange_rate(&self) -> Option<ExchangeRate> {
+            self.exchange_rate.get()
+        }
+
+        /// Function to set how old the exchange rate and token rates may get before prices
+        /// fail (only price setter).
+        #[ink(message)]
+        pub fn set_max_rate_age(&mut self, max_rate_age: Timestamp) -> Result<()> {
+            self.ensure_role(PRICE_SETTER)?;
+            self.max_rate_age.set(&max_rate_age);
+            Ok(())
+        }
+
+        /// Function to get how old the exchange rate and token rates may get before prices fail.
+        #[ink(message)]
+        pub fn get_max_rate_age(&self) -> Timestamp {
+            self.max_rate_age.get().unwrap_or(DEFAULT_MAX_RATE_AGE)
+        }
+
+        fn current_rate(&self) -> core::result::Result<Balance, PriceError> {
+            let rate = self.exchange_rate.get().ok_or(PriceError::NoExchangeRate)?;
+            if self.is_stale(rate.updated_at) {
+                return Err(PriceError::StaleExchangeRate);
+            }
+            Ok(rate.native_per_usd)
+        }
+
+        fn is_stale(&self, updated_at: Timestamp) -> bool {
+            self.env().block_timestamp().saturating_sub(updated_at) > self.get_max_rate_age()
+        }
+
+        /// Prices a name in USD cents.
+        fn quote_usd(
+            &self,
+            name: ink::prelude::string::String,
+            duration: Timestamp,
+        ) -> core::result::Result<PriceQuote, PriceError> {
+            let name = name::fold(&name);
+            let name_length = name.chars().count() as u128; // Get the number of characters in the name
+
+            if duration == 0 {
+                return Err(PriceError::ZeroDuration);
+            }
+            // older code left prices in the native balance until `migrate` converts them
+            if self.storage_version < STORAGE_VERSION {
+                return Err(PriceError::NotMigrated);
+            }
+
+            let tld = ink::prelude::string::String::from(name::tld_of(&name));
+            if self.tld_pricing.contains(&tld) {
+                return Err(PriceError::NotMigrated);
+            }
+            let pricing = self.usd_tld_pricing.get(tld).unwrap_or(TldPricing {
+                price_per_letter: self.price_per_letter,
+                price_per_year: self.price_per_year,
+            });
 
             // Checked multiplication for price based on the number of letters
-            let no_of_words_price = name_length.checked_mul(self.price_per_letter)?;
+            let no_of_words_price = name_length
+                .checked_mul(pricing.price_per_letter)
+                .ok_or(PriceError::Overflow)?;
 
             // Checked multiplication for price based on the duration in years
             let num_years_price = (duration as u128)
-                .checked_mul(self.price_per_year)?
-                .checked_div(365 * 24 * 60 * 60)?;
+                .checked_mul(pricing.price_per_year)
+                .ok_or(PriceError::Overflow)?
+                / (365 * 24 * 60 * 60);
 
-            // Checked addition for total price
-            let mut total_price = no_of_words_price.checked_add(num_years_price)?;
+            // Multiply price by 10 if it's a premium name
+            let premium_multiplier = if self.is_premium_name(name.clone()) {
+                10
+            } else {
+                1
+            };
 
-            // Check if the name is in the premium names list
-            if self.premium_names.contains(&name) {
-                total_price = total_price.checked_mul(10)?; // Multiply price by 10 if it's a premium name
+            let premium = self.release_premium_of(name);
+
+            PriceQuote::new(
+                no_of_words_price,
+                num_years_price,
+                premium_multiplier,
+                premium,
+            )
+        }
+
+        /// Function to report the exchange rate of a PSP22 token, `None` removes it (only rate
+        /// reporter). Like the exchange rate it has to be reported again before it goes stale.
+        #[ink(message)]
+        pub fn set_token_rate(&mut self, token: AccountId, rate: Option<TokenRate>) -> Result<()> {
+            self.ensure_role(RATE_REPORTER)?;
+            match rate {
+                Some(rate) if rate.tokens == 0 || rate.native == 0 => return Err(Error::ZeroRate),
+                Some(rate) => {
+                    self.token_rates.insert(
+                        token,
+                        &ReportedTokenRate {
+                            rate,
+                            updated_at: self.env().block_timestamp(),
+                        },
+                    );
+                }
+                None => self.token_rates.remove(token),
             }
+            Ok(())
+        }
 
-            // Return the total price, if all operations succeeded
-            Some(total_price)
+        /// Function to get the last reported exchange rate of a PSP22 token.
+        #[ink(message)]
+        pub fn get_token_rate(&self, token: AccountId) -> Option<ReportedTokenRate> {
+            self.token_rates.get(token)
         }
 
         #[ink(message)]
         pub fn read_owner(&self) -> AccountId {
-            self.owner
+            self.access.admin()
         }
+
+        /// Function to replace the code of the contract, keeping its storage (only owner).
+        /// Call `migrate` afterwards.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+            self.env().emit_event(Upgraded { code_hash });
+            Ok(())
+        }
+
+        /// Function to convert storage written by older code to the layout of this one after `upgrade` (only owner).
+        /// Version 1 kept prices in the native balance, which are converted to USD cents with the
+        /// exchange rate of the first call, so report one first. The pricing of TLDs cannot be
+        /// listed on chain, so the TLDs to convert are passed in, over as many calls as it takes;
+        /// quotes for them fail until then. Returns the number of converted TLDs.
+        #[ink(message)]
+        pub fn migrate(
+            &mut self,
+            tlds: ink::prelude::vec::Vec<ink::prelude::string::String>,
+        ) -> Result<u32> {
+            self.ensure_role(ADMIN)?;
+            let from_version = self.storage_version;
+
+            // version 2 prices in USD cents instead of the native balance
+            let native_per_usd = match self.migration_rate.get() {
+                Some(native_per_usd) => native_per_usd,
+                None if from_version < STORAGE_VERSION => self.current_rate()?,
+                // created at the current version, so nothing was left in the native balance
+                None => return Err(Error::AlreadyMigrated),
+            };
+            if from_version < STORAGE_VERSION {
+                self.price_per_letter = to_cents(self.price_per_letter, native_per_usd)?;
+                self.price_per_year = to_cents(self.price_per_year, native_per_usd)?;
+                if let Some(release_premium) = self.release_premium.as_mut() {
+                    release_premium.start_premium =
+                        to_cents(release_premium.start_premium, native_per_usd)?;
+                }
+                self.migration_rate.set(&native_per_usd);
+            }
+            let mut converted: u32 = 0;
+            for tld in tlds {
+                let tld = name::fold(&tld);
+                if let Some(pricing) = self.tld_pricing.take(&tld) {
+                    let pricing = TldPricing {
+                        price_per_letter: to_cents(pricing.price_per_letter, native_per_usd)?,
+                        price_per_year: to_cents(pricing.price_per_year, native_per_usd)?,
+                    };
+                    self.usd_tld_pricing.insert(tld, &pricing);
+                    converted = converted.saturating_add(1);
+                }
+            }
+
+            if from_version < STORAGE_VERSION {
+                self.storage_version = STORAGE_VERSION;
+                self.env().emit_event(Migrated {
+                    from_version,
+                    to_version: STORAGE_VERSION,
+                });
+            } else if converted == 0 {
+                return Err(Error::AlreadyMigrated);
+            }
+            Ok(converted)
+        }
+
+        /// Function to get the storage layout version the storage was last migrated to.
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            self.storage_version
+        }
+
+        /// Function to get the proposed next owner, if any.
+        #[ink(message)]
+        pub fn read_pending_owner(&self) -> Option<AccountId> {
+            self.access.pending_admin()
+        }
+
+        /// Function to check whether an account has a role.
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+            self.access.has_role(role, account)
+        }
+
+        /// Function to grant a role to an account (only owner).
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.grant_role(sender, role, account)?;
+            self.env().emit_event(RoleGranted {
+                account,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Function to revoke a role from an account (only owner).
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.revoke_role(sender, role, account)?;
+            self.env().emit_event(RoleRevoked {
+                account,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Function to give up a role of the caller.
+        #[ink(message)]
+        pub fn renounce_role(&mut self, role: Role) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.renounce_role(sender, role)?;
+            self.env().emit_event(RoleRevoked {
+                account: sender,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Function to propose the next owner, who takes over by calling `accept_ownership` (only owner).
+        #[ink(message)]
+        pub fn propose_owner(&mut self, pending_owner: AccountId) -> Result<()> {
+            self.access
+                .propose_admin(self.env().caller(), pending_owner)?;
+            self.env().emit_event(AdminTransferProposed {
+                admin: self.access.admin(),
+                pending_admin: pending_owner,
+            });
+            Ok(())
+        }
+
+        /// Function to take over ownership (only proposed owner).
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+            let admin = self.env().caller();
+            let previous_admin = self.access.accept_admin(admin)?;
+            self.env().emit_event(AdminTransferred {
+                previous_admin,
+                admin,
+            });
+            Ok(())
+        }
+
+        fn release_premium_of(&self, name: ink::prelude::string::String) -> Balance {
+            let (Some(release_premium), Some(released_at)) =
+                (&self.release_premium, self.release_times.get(name))
+            else {
+                return 0;
+            };
+            let elapsed = self.env().block_timestamp().saturating_sub(released_at);
+            if elapsed >= release_premium.duration {
+                return 0;
+            }
+
+            match release_premium.decay {
+                PremiumDecay::Linear => {
+                    let remaining = (release_premium.duration - elapsed) as u128;
+                    release_premium.start_premium.saturating_mul(remaining)
+                        / release_premium.duration as u128
+                }
+                PremiumDecay::Exponential { half_life } => {
+                    let start = release_premium.start_premium;
+                    exponential_decay(start, elapsed, half_life).saturating_sub(exponential_decay(
+                        start,
+                        release_premium.duration,
+                        half_life,
+                    ))
+                }
+            }
+        }
+    }
+
+    impl VnsPriceOracle for Priceoracle {
+        /// Function to quote the price in the native balance, itemized. Names are case folded
+        /// and normalized first, then priced in USD cents with the pricing of their TLD or the
+        /// default one and converted with the exchange rate, which must not be stale.
+        #[ink(message)]
+        fn quote(
+            &self,
+            name: ink::prelude::string::String,
+            duration: Timestamp,
+        ) -> core::result::Result<PriceQuote, PriceError> {
+            let usd_quote = self.quote_usd(name, duration)?;
+            let native_per_usd = self.current_rate()?;
+            PriceQuote::new(
+                to_native(usd_quote.length, native_per_usd)?,
+                to_native(usd_quote.duration, native_per_usd)?,
+                usd_quote.premium_multiplier,
+                to_native(usd_quote.premium, native_per_usd)?,
+            )
+        }
+
+        /// Function to convert a native `amount` to `token`, rounded up. `None` if the token
+        /// has no rate, its rate is stale or the result overflows.
+        #[ink(message)]
+        fn convert_to_token(&self, token: AccountId, amount: Balance) -> Option<Balance> {
+            let reported = self.token_rates.get(token)?;
+            if self.is_stale(reported.updated_at) {
+                return None;
+            }
+            let rate = reported.rate;
+            amount
+                .checked_mul(rate.tokens)?
+                .checked_add(rate.native - 1)?
+                .checked_div(rate.native)
+        }
+
+        /// Function to check whether a name is a premium name (public).
+        #[ink(message)]
+        fn is_premium_name(&self, name: ink::prelude::string::String) -> bool {
+            let name = name::fold(&name);
+            self.premium_names
+                .iter()
+                .any(|premium_name| name::fold(premium_name) == name)
+        }
+
+        /// Function to record that a name was released, called by the resolver when it
+        /// unregisters an expired domain (only manager).
+        #[ink(message)]
+        fn record_release(
+            &mut self,
+            name: ink::prelude::string::String,
+            released_at: Timestamp,
+        ) -> Result<()> {
+            self.ensure_role(MANAGER)?;
+            self.release_times.insert(name::fold(&name), &released_at);
+            Ok(())
+        }
+    }
+
+    /// Converts USD `cents` to the native balance, rounded up.
+    fn to_native(
+        cents: Balance,
+        native_per_usd: Balance,
+    ) -> core::result::Result<Balance, PriceError> {
+        cents
+            .checked_mul(native_per_usd)
+            .and_then(|amount| amount.checked_add(99))
+            .map(|amount| amount / 100)
+            .ok_or(PriceError::Overflow)
+    }
+
+    /// Converts a native `amount` to USD cents, rounded up.
+    fn to_cents(
+        amount: Balance,
+        native_per_usd: Balance,
+    ) -> core::result::Result<Balance, PriceError> {
+        amount
+            .checked_mul(100)
+            .and_then(|cents| cents.checked_add(native_per_usd - 1))
+            .map(|cents| cents / native_per_usd)
+            .ok_or(PriceError::Overflow)
+    }
+
+    /// Halves `start` every `half_life`, interpolating linearly in between.
+    fn exponential_decay(start: Balance, elapsed: Timestamp, half_life: Timestamp) -> Balance {
+        if half_life == 0 || elapsed / half_life >= Balance::BITS as u64 {
+            return 0;
+        }
+        let whole = start >> (elapsed / half_life);
+        let fraction = (elapsed % half_life) as u128;
+        whole - (whole / 2).saturating_mul(fraction) / half_life as u128
     }
 
     #[cfg(test)]
     mod tests {
         use super::*;
+        use vns_common::access::AccessError;
+        use vns_common::testing::set_caller;
+
+        /// An oracle whose prices in the native balance equal those in cents.
+        fn new_oracle() -> Priceoracle {
+            let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            contract.report_exchange_rate(100).unwrap();
+            contract
+        }
 
         #[ink::test]
         fn default_works() {
             let contract = Priceoracle::new(AccountId::from([0x1; 20]));
-            assert_eq!(contract.price_per_letter, 1 * 10u128.pow(18));
-            assert_eq!(contract.price_per_year, 20 * 10u128.pow(18));
+            assert_eq!(contract.price_per_letter, 100);
+            assert_eq!(contract.price_per_year, 2000);
         }
 
         #[ink::test]
         fn set_price_works() {
             let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
-            contract.set_price_per_letter(2);
-            contract.set_price_per_year(30);
+            contract.set_price_per_letter(2).unwrap();
+            contract.set_price_per_year(30).unwrap();
             assert_eq!(contract.price_per_letter, 2);
             assert_eq!(contract.price_per_year, 30);
         }
 
         #[ink::test]
         fn calculate_price_works() {
-            let contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            let contract = new_oracle();
             let price = contract.calculate_price("Alice".to_string(), 31536000); // 1 year
-            assert_eq!(price, Some((5 * 1 + 20) * 10u128.pow(18))); // 5 letters * 1 Ether + 20 Ether for 1 year
+            assert_eq!(price.map(|price| price.total), Ok(5 * 100 + 2000)); // 5 letters * 1 USD + 20 USD for 1 year
         }
 
         #[ink::test]
         fn add_premium_name_works() {
             let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
-            contract.add_premium_name("Alice".to_string());
+            contract.add_premium_name("Alice".to_string()).unwrap();
             assert_eq!(contract.premium_names.len(), 1);
             assert_eq!(contract.premium_names[0], "Alice");
+            assert!(contract.is_premium_name("alice".to_string()));
         }
 
         #[ink::test]
         fn remove_premium_name_works() {
             let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
-            contract.add_premium_name("Alice".to_string());
-            assert!(contract.remove_premium_name("Alice".to_string()));
+            contract.add_premium_name("Alice".to_string()).unwrap();
+            assert_eq!(contract.remove_premium_name("Alice".to_string()), Ok(true));
             assert_eq!(contract.premium_names.len(), 0);
         }
+
+        #[ink::test]
+        fn tld_pricing_works() {
+            let mut contract = new_oracle();
+            contract
+                .set_tld_pricing("DAO".to_string(), 2, 365 * 24 * 60 * 60)
+                .unwrap();
+            assert_eq!(
+                contract.get_tld_pricing("dao".to_string()),
+                Some(TldPricing {
+                    price_per_letter: 2,
+                    price_per_year: 365 * 24 * 60 * 60,
+                })
+            );
+
+            // 9 letters * 2 + 1 per second for 100 seconds
+            assert_eq!(
+                contract
+                    .calculate_price("alice.dao".to_string(), 100)
+                    .map(|price| price.total),
+                Ok(9 * 2 + 100)
+            );
+            // other TLDs keep the default pricing
+            assert_eq!(
+                contract
+                    .calculate_price("alice.vne".to_string(), 31536000)
+                    .map(|price| price.total),
+                Ok(9 * 100 + 2000)
+            );
+
+            assert_eq!(contract.remove_tld_pricing("dao".to_string()), Ok(true));
+            assert_eq!(contract.get_tld_pricing("dao".to_string()), None);
+        }
+
+        #[ink::test]
+        fn linear_release_premium_works() {
+            let mut contract = new_oracle();
+            contract
+                .set_release_premium(Some(ReleasePremium {
+                    start_premium: 1000,
+                    duration: 100,
+                    decay: PremiumDecay::Linear,
+                }))
+                .unwrap();
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            contract
+                .record_release("alice.vne".to_string(), 1000)
+                .unwrap();
+
+            let price = contract
+                .calculate_price("alice.vne".to_string(), 31536000)
+                .unwrap();
+            assert_eq!(price.premium, 1000);
+            assert_eq!(price.total, price.base + 1000);
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1025);
+            assert_eq!(
+                contract.calculate_release_premium("alice.vne".to_string()),
+                750
+            );
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1100);
+            assert_eq!(
+                contract.calculate_release_premium("alice.vne".to_string()),
+                0
+            );
+            // names that were never released have no premium
+            assert_eq!(contract.calculate_release_premium("bob.vne".to_string()), 0);
+        }
+
+        #[ink::test]
+        fn exponential_release_premium_works() {
+            let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            contract
+                .set_release_premium(Some(ReleasePremium {
+                    start_premium: 1024,
+                    duration: 100,
+                    decay: PremiumDecay::Exponential { half_life: 10 },
+                }))
+                .unwrap();
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            contract
+                .record_release("alice.vne".to_string(), 1000)
+                .unwrap();
+
+            // 1024 at release and 1 at the end, shifted down by that 1
+            assert_eq!(
+                contract.calculate_release_premium("alice.vne".to_string()),
+                1023
+            );
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1010);
+            assert_eq!(
+                contract.calculate_release_premium("alice.vne".to_string()),
+                511
+            );
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1015);
+            assert_eq!(
+                contract.calculate_release_premium("alice.vne".to_string()),
+                383
+            );
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1100);
+            assert_eq!(
+                contract.calculate_release_premium("alice.vne".to_string()),
+                0
+            );
+        }
+
+        #[ink::test]
+        fn only_price_setters_can_set_prices() {
+            let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            let price_setter = AccountId::from([0x2; 20]);
+            set_caller(price_setter);
+            assert_eq!(
+                contract.set_price_per_letter(2),
+                Err(Error::Access(AccessError::MissingRole {
+                    role: PRICE_SETTER
+                }))
+            );
+            assert_eq!(
+                contract.record_release("alice.vne".to_string(), 1000),
+                Err(Error::Access(AccessError::MissingRole { role: MANAGER }))
+            );
+
+            set_caller(AccountId::from([0x1; 20]));
+            assert_eq!(contract.grant_role(PRICE_SETTER, price_setter), Ok(()));
+            set_caller(price_setter);
+            assert_eq!(contract.set_price_per_letter(2), Ok(()));
+            assert_eq!(contract.price_per_letter, 2);
+        }
+
+        #[ink::test]
+        fn ownership_transfer_takes_two_steps() {
+            let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            let new_owner = AccountId::from([0x2; 20]);
+            assert_eq!(contract.propose_owner(new_owner), Ok(()));
+            assert_eq!(contract.read_owner(), AccountId::from([0x1; 20]));
+
+            set_caller(new_owner);
+            assert_eq!(contract.accept_ownership(), Ok(()));
+            assert_eq!(contract.read_owner(), new_owner);
+            assert_eq!(contract.read_pending_owner(), None);
+        }
+
+        #[ink::test]
+        fn migrate_converts_native_prices() {
+            let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            assert_eq!(contract.storage_version(), STORAGE_VERSION);
+            assert_eq!(contract.migrate(vec![]), Err(Error::AlreadyMigrated));
+
+            // as left behind by version 1 code, in the native balance
+            let native = 10u128.pow(18);
+            contract.storage_version = 1;
+            contract.price_per_letter = native;
+            contract.price_per_year = 20 * native;
+            contract.tld_pricing.insert(
+                "dao".to_string(),
+                &TldPricing {
+                    price_per_letter: 2 * native,
+                    price_per_year: native / 2,
+                },
+            );
+            contract.tld_pricing.insert(
+                "xyz".to_string(),
+                &TldPricing {
+                    price_per_letter: 3 * native,
+                    price_per_year: native,
+                },
+            );
+            contract.release_premium = Some(ReleasePremium {
+                start_premium: 5 * native,
+                duration: 100,
+                decay: PremiumDecay::Linear,
+            });
+            assert_eq!(
+                contract.quote("alice.vne".to_string(), 31536000),
+                Err(PriceError::NotMigrated)
+            );
+            let not_migrated = Err(Error::Price(PriceError::NotMigrated));
+            assert_eq!(contract.set_price_per_letter(100), not_migrated);
+            assert_eq!(contract.set_price_per_year(2000), not_migrated);
+            assert_eq!(
+                contract.set_tld_pricing("abc".to_string(), 1, 1),
+                not_migrated
+            );
+            assert_eq!(contract.set_release_premium(None), not_migrated);
+            assert_eq!(
+                contract.migrate(vec![]),
+                Err(Error::Price(PriceError::NoExchangeRate))
+            );
+
+            // 1 USD is worth 10^18 native
+            contract.report_exchange_rate(native).unwrap();
+            assert_eq!(contract.migrate(vec!["DAO".to_string()]), Ok(1));
+            assert_eq!(contract.storage_version(), STORAGE_VERSION);
+            assert_eq!(
+                contract.quote("alice.xyz".to_string(), 31536000),
+                Err(PriceError::NotMigrated)
+            );
+            assert_eq!(contract.get_tld_pricing("xyz".to_string()), None);
+
+            // later calls keep the rate of the first one and skip converted TLDs
+            contract.report_exchange_rate(2 * native).unwrap();
+            assert_eq!(
+                contract.migrate(vec!["xyz".to_string(), "dao".to_string()]),
+                Ok(1)
+            );
+            assert_eq!(
+                contract.get_tld_pricing("xyz".to_string()),
+                Some(TldPricing {
+                    price_per_letter: 300,
+                    price_per_year: 100,
+                })
+            );
+            assert_eq!(
+                contract.migrate(vec!["xyz".to_string()]),
+                Err(Error::AlreadyMigrated)
+            );
+            contract.report_exchange_rate(native).unwrap();
+            assert_eq!(contract.get_price_per_letter(), 100);
+            assert_eq!(contract.get_price_per_year(), 2000);
+            assert_eq!(
+                contract.get_tld_pricing("dao".to_string()),
+                Some(TldPricing {
+                    price_per_letter: 200,
+                    price_per_year: 50,
+                })
+            );
+            assert_eq!(
+                contract
+                    .get_release_premium()
+                    .map(|premium| premium.start_premium),
+                Some(500)
+            );
+            assert_eq!(
+                contract
+                    .calculate_price("alice.vne".to_string(), 31536000)
+                    .map(|price| price.total),
+                Ok((9 + 20) * native)
+            );
+            assert_eq!(contract.migrate(vec![]), Err(Error::AlreadyMigrated));
+
+            set_caller(AccountId::from([0x2; 20]));
+            let missing_admin = || Error::Access(AccessError::MissingRole { role: ADMIN });
+            assert_eq!(contract.upgrade([0x5; 32]), Err(missing_admin()));
+            assert_eq!(contract.migrate(vec![]), Err(missing_admin()));
+        }
+
+        #[ink::test]
+        fn token_rate_works() {
+            let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            let token = AccountId::from([0x5; 20]);
+            assert_eq!(contract.convert_to_token(token, 100), None);
+            assert_eq!(
+                contract.set_token_rate(
+                    token,
+                    Some(TokenRate {
+                        tokens: 3,
+                        native: 0
+                    })
+                ),
+                Err(Error::ZeroRate)
+            );
+
+            // 3 tokens are worth 2 native
+            contract
+                .set_token_rate(
+                    token,
+                    Some(TokenRate {
+                        tokens: 3,
+                        native: 2,
+                    }),
+                )
+                .unwrap();
+            assert_eq!(contract.convert_to_token(token, 100), Some(150));
+            // rounded up, in favour of the registrar
+            assert_eq!(contract.convert_to_token(token, 101), Some(152));
+            assert_eq!(contract.convert_to_token(token, Balance::MAX), None);
+
+            contract.set_token_rate(token, None).unwrap();
+            assert_eq!(contract.get_token_rate(token), None);
+
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                contract.set_token_rate(
+                    token,
+                    Some(TokenRate {
+                        tokens: 1,
+                        native: 1
+                    })
+                ),
+                Err(Error::Access(AccessError::MissingRole {
+                    role: RATE_REPORTER
+                }))
+            );
+        }
+
+        #[ink::test]
+        fn stale_token_rate_fails() {
+            let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            let token = AccountId::from([0x5; 20]);
+            contract.set_max_rate_age(100).unwrap();
+            contract
+                .set_token_rate(
+                    token,
+                    Some(TokenRate {
+                        tokens: 1,
+                        native: 1,
+                    }),
+                )
+                .unwrap();
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(100);
+            assert_eq!(contract.convert_to_token(token, 100), Some(100));
+            ink::env::test::set_block_timestamp::<MyEnvironment>(101);
+            assert_eq!(contract.convert_to_token(token, 100), None);
+
+            // a fresh report makes conversions available again
+            contract
+                .set_token_rate(
+                    token,
+                    Some(TokenRate {
+                        tokens: 1,
+                        native: 1,
+                    }),
+                )
+                .unwrap();
+            assert_eq!(
+                contract.get_token_rate(token),
+                Some(ReportedTokenRate {
+                    rate: TokenRate {
+                        tokens: 1,
+                        native: 1
+                    },
+                    updated_at: 101,
+                })
+            );
+            assert_eq!(contract.convert_to_token(token, 100), Some(100));
+        }
+
+        #[ink::test]
+        fn calculate_price_converts_usd() {
+            let mut contract = Priceoracle::new(AccountId::from([0x1; 20]));
+            assert_eq!(
+                contract.calculate_price("alice.vne".to_string(), 31536000),
+                Err(PriceError::NoExchangeRate)
+            );
+            assert_eq!(contract.report_exchange_rate(0), Err(Error::ZeroRate));
+
+            // 1 USD is worth 10^18 native
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            contract.report_exchange_rate(10u128.pow(18)).unwrap();
+            assert_eq!(
+                contract
+                    .calculate_price("alice.vne".to_string(), 31536000)
+                    .map(|price| price.total),
+                Ok((9 + 20) * 10u128.pow(18))
+            );
+            // conversions round up
+            assert_eq!(to_native(1, 150), Ok(2));
+
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                contract.report_exchange_rate(1),
+                Err(Error::Access(AccessError::MissingRole {
+                    role: RATE_REPORTER
+                }))
+            );
+        }
+
+        #[ink::test]
+        fn stale_exchange_rate_fails() {
+            let mut contract = new_oracle();
+            contract.set_max_rate_age(100).unwrap();
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(100);
+            assert!(contract
+                .calculate_price("alice.vne".to_string(), 31536000)
+                .is_ok());
+            ink::env::test::set_block_timestamp::<MyEnvironment>(101);
+            assert_eq!(
+                contract.calculate_price("alice.vne".to_string(), 31536000),
+                Err(PriceError::StaleExchangeRate)
+            );
+
+            // a fresh report makes prices available again
+            contract.report_exchange_rate(100).unwrap();
+            assert!(contract
+                .calculate_price("alice.vne".to_string(), 31536000)
+                .is_ok());
+        }
+
+        #[ink::test]
+        fn quote_is_itemized() {
+            let mut contract = new_oracle();
+            contract.add_premium_name("alice.vne".to_string()).unwrap();
+            contract
+                .set_release_premium(Some(ReleasePremium {
+                    start_premium: 1000,
+                    duration: 100,
+                    decay: PremiumDecay::Linear,
+                }))
+                .unwrap();
+            contract.record_release("alice.vne".to_string(), 0).unwrap();
+
+            assert_eq!(
+                contract.quote("Alice.vne".to_string(), 31536000),
+                Ok(PriceQuote {
+                    length: 9 * 100,
+                    duration: 2000,
+                    premium_multiplier: 10,
+                    premium: 1000,
+                    discount: 0,
+                    total: (9 * 100 + 2000) * 10 + 1000,
+                })
+            );
+            assert_eq!(
+                contract
+                    .calculate_price("alice.vne".to_string(), 31536000)
+                    .map(|price| price.base),
+                Ok((9 * 100 + 2000) * 10)
+            );
+        }
+
+        #[ink::test]
+        fn quote_fails_with_typed_errors() {
+            let mut contract = new_oracle();
+            assert_eq!(
+                contract.quote("alice.vne".to_string(), 0),
+                Err(PriceError::ZeroDuration)
+            );
+            contract.set_price_per_letter(Balance::MAX).unwrap();
+            assert_eq!(
+                contract.quote("alice.vne".to_string(), 31536000),
+                Err(PriceError::Overflow)
+            );
+        }
     }
 }
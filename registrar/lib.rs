@@ -2,39 +2,68 @@
 
 #[ink::contract(env = MyEnvironment)]
 pub mod registrar {
-    #[derive(Clone)]
-    pub struct MyEnvironment;
-    impl ink::env::Environment for MyEnvironment {
-        const MAX_EVENT_TOPICS: usize = 3;
-        type AccountId = [u8; 20];
-        type Balance = u128;
-        type Hash = [u8; 32];
-        type Timestamp = u64;
-        type BlockNumber = u32;
-        type ChainExtension = ::ink::env::NoChainExtension;
-    }
+    pub type MyEnvironment = vns_common::VnsEnvironment;
 
+    use core::ops::Add;
+    use ink::codegen::TraitCallBuilder;
+    use ink::contract_ref;
+    use ink::env::call::{ExecutionInput, FromAccountId, Selector};
     use ink::prelude::string::String;
+    use ink::prelude::vec;
+    use ink::prelude::vec::Vec;
     use ink::{
         env::hash::{HashOutput, Sha2x256},
-        storage::Mapping,
+        storage::{Lazy, Mapping},
     };
-    use core::ops::Add;
-    use ink::env::call::{build_call, ExecutionInput, Selector};
-
+    use vns_common::access::{
+        AccessControl, AccessError, AdminTransferProposed, AdminTransferred, Role, RoleGranted,
+        RoleRevoked, ADMIN, PAUSER, TREASURER,
+    };
+    use vns_common::call::{self, CallError, CallLimits, CallLimitsSet};
+    use vns_common::merkle;
+    use vns_common::name::{self, InvalidNameReason};
+    use vns_common::nft::{NftError, VnsNft};
+    use vns_common::price::VnsPriceOracle;
+    use vns_common::price::{PriceError, PriceQuote};
+    use vns_common::record::RecordEntry;
+    use vns_common::resolver::{ResolverError, VnsResolver};
+    use vns_common::token::PSP22Error;
 
     #[ink(storage)]
     pub struct Registrar {
-        commitments: Mapping<Hash, Timestamp>,
+        commitments: Mapping<Hash, Commitment>,
         commit_info: Mapping<Hash, CommitInfo>,
-        admin: AccountId,
+        access: AccessControl,
         max_commit_age: u64,
         min_commit_age: u64,
-        min_registration_duration: u64,
+        tlds: Mapping<String, TldConfig>,
+        supported_tlds: Vec<String>,
         resolver_contract_address: AccountId,
         price_oracle_contract_address: AccountId,
         erc721: AccountId,
         token_id: u64,
+        domain_tokens: Mapping<String, u64>,
+        reserved_names: Mapping<String, ()>,
+        auction_config: Option<AuctionConfig>,
+        auctions: Mapping<String, Auction>,
+        sealed_bids: Mapping<(u64, AccountId), SealedBid>,
+        auction_count: u64,
+        fee_split: FeeSplit,
+        balances: Mapping<AccountId, Balance>,
+        treasury_balance: Balance,
+        revenue_tlds: Vec<String>,
+        paused: bool,
+        tld_revenue: Mapping<String, Revenue>,
+        period_revenue: Mapping<(String, u64), Revenue>,
+        launch_schedule: Lazy<LaunchSchedule>,
+        vouchers: Mapping<Hash, Voucher>,
+        payment_tokens: Mapping<AccountId, ()>,
+        token_balances: Mapping<(AccountId, AccountId), Balance>,
+        token_treasury: Mapping<AccountId, Balance>,
+        token_revenue: Mapping<AccountId, Revenue>,
+        call_limits: Lazy<CallLimits>,
+        /// Allowlist leaves used up by a registration, by the root of their tree.
+        redeemed_leaves: Mapping<(Hash, Hash), ()>,
     }
 
     #[derive(scale::Decode, scale::Encode, Debug, Clone)]
@@ -48,6 +77,212 @@ pub mod registrar {
         duration: Timestamp,
         secret: [u8; 32],
         resolver: AccountId,
+        /// The account that commits and registers, so a copied commitment is of no use.
+        committer: AccountId,
+    }
+
+    #[derive(scale::Decode, scale::Encode, Debug, Clone)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Commitment {
+        committed_at: Timestamp,
+        committer: AccountId,
+    }
+
+    #[derive(scale::Decode, scale::Encode, Debug, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct RegistrationRequest {
+        pub domain_name: String,
+        pub domain_owner: AccountId,
+        pub duration: Timestamp,
+        pub secret: [u8; 32],
+        pub resolver: AccountId,
+        /// Mints the domain NFT with this token uri when set.
+        pub token_uri: Option<String>,
+        /// Earns the referral share of the registration fee when set.
+        pub referrer: Option<AccountId>,
+        /// Required while registration is limited to an allowlist.
+        pub allowlist_proof: Option<AllowlistProof>,
+        /// Voucher code to redeem for a lower price.
+        pub voucher: Option<String>,
+        /// The most the caller is willing to pay, in the currency paid with.
+        pub max_price: Option<Balance>,
+        /// Written to the resolver along with the registration. The commitment has
+        /// to cover them, see `make_commitment_with_records`.
+        pub records: Vec<RecordEntry>,
+    }
+
+    #[derive(scale::Decode, scale::Encode, Debug, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct RenewalRequest {
+        pub domain_name: String,
+        pub duration: Timestamp,
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum CommitmentStatus {
+        Pending { ready_at: Timestamp },
+        Ready { expires_at: Timestamp },
+        Expired,
+        Unknown,
+    }
+
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct TldConfig {
+        min_registration_duration: Timestamp,
+    }
+
+    /// Timing of premium name auctions and how long the winner gets the name for.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct AuctionConfig {
+        pub bidding_period: Timestamp,
+        pub reveal_period: Timestamp,
+        pub registration_duration: Timestamp,
+    }
+
+    /// A sealed-bid second-price auction of a premium name.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Auction {
+        id: u64,
+        bidding_ends: Timestamp,
+        reveal_ends: Timestamp,
+        registration_duration: Timestamp,
+        /// The oracle price of the name, bids below it do not count.
+        reserve_price: Balance,
+        highest_bidder: Option<AccountId>,
+        highest_bid: Balance,
+        highest_deposit: Balance,
+        /// What the winner pays: the second highest bid, at least the reserve price.
+        second_bid: Balance,
+    }
+
+    #[derive(scale::Decode, scale::Encode, Debug, Clone)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct SealedBid {
+        bid_hash: Hash,
+        deposit: Balance,
+        reveal_ends: Timestamp,
+    }
+
+    /// How fees are split, in basis points. The treasury, the admin, gets
+    /// whatever is not paid out to the referrer or the partner.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct FeeSplit {
+        /// Share of registration fees paid to the referrer, if there is one.
+        pub referrer_bps: u16,
+        /// Share of all fees paid to the partner, if there is one.
+        pub partner_bps: u16,
+        pub partner: Option<AccountId>,
+    }
+
+    /// An early access phase in which only allowlisted accounts may register.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct AllowlistPhase {
+        pub starts_at: Timestamp,
+        /// Root of the tree of `make_allowlist_leaf` leaves.
+        pub merkle_root: Hash,
+        /// Discount on the registration price, in basis points.
+        pub discount_bps: u16,
+    }
+
+    /// Allowlist phases, by start time, until registration opens to everyone.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct LaunchSchedule {
+        pub phases: Vec<AllowlistPhase>,
+        pub public_opens_at: Timestamp,
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum RegistrationPhase {
+        /// Before the first allowlist phase.
+        Closed,
+        Allowlist {
+            index: u32,
+        },
+        Public,
+    }
+
+    /// Proof that the caller is on the allowlist of the current phase.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct AllowlistProof {
+        /// The only name the allowlist entry may register, `None` for any name.
+        pub name: Option<String>,
+        pub proof: Vec<Hash>,
+    }
+
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Discount {
+        /// Off the price, in basis points.
+        Percent { bps: u16 },
+        /// Off the price, at most the whole price.
+        Fixed { amount: Balance },
+    }
+
+    /// A voucher, stored under the hash of its code so the code stays secret.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Voucher {
+        pub discount: Discount,
+        pub max_uses: u32,
+        pub redeemed: u32,
+        pub expires_at: Timestamp,
+    }
+
+    /// Fees collected for a TLD and the part of them kept by the treasury.
+    #[derive(scale::Decode, scale::Encode, Debug, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Revenue {
+        pub fees: Balance,
+        pub treasury: Balance,
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct TldRevenue {
+        pub tld: String,
+        pub revenue: Revenue,
     }
 
     #[derive(scale::Decode, scale::Encode, Debug, Clone)]
@@ -72,12 +307,118 @@ pub mod registrar {
         AlreadyRegistered,
         DomainNotRegistered,
         DomainNotExpired,
+        InsufficientPayment,
+        PriceUnavailable,
+        TransferFailed,
+        NotCommitter,
+        MintFailed,
+        NftAlreadyMinted,
+        InvalidName {
+            reason: InvalidNameReason,
+        },
+        UnsupportedTld,
+        TldAlreadySupported,
+        NameReserved,
+        NameNotReserved,
+        AuctionsDisabled,
+        NotPremiumName,
+        NameInAuction,
+        AuctionNotFound,
+        BiddingClosed,
+        NotInRevealPhase,
+        AuctionNotEnded,
+        AlreadyBid,
+        BidNotFound,
+        InvalidReveal,
+        InvalidFeeSplit,
+        InvalidReferrer,
+        NothingToWithdraw,
+        InsufficientTreasury,
+        Paused,
+        Access(AccessError),
+        UpgradeFailed,
+        InvalidSchedule,
+        RegistrationNotOpen,
+        ProofRequired,
+        InvalidProof,
+        InvalidDiscount,
+        VoucherNotFound,
+        VoucherExpired,
+        VoucherExhausted,
+        UnsupportedToken,
+        /// The price rose above the request's `max_price`.
+        PriceAboveMax,
+        /// The price oracle could not quote a price.
+        Price(PriceError),
+        /// A call to another contract failed.
+        Call(CallError),
+        /// Premium names can only be won in an auction while auctions are configured.
+        AuctionRequired,
+        /// The resolver rejected the registration.
+        Resolver(ResolverError),
+        /// The allowlist entry was already used to register a name.
+        AllowlistEntryRedeemed,
+        /// The NFT rejected the mint.
+        Nft(NftError),
+        /// The payment token rejected a transfer.
+        Token(PSP22Error),
+    }
+
+    impl From<AccessError> for Error {
+        fn from(error: AccessError) -> Self {
+            Error::Access(error)
+        }
+    }
+
+    impl From<NftError> for Error {
+        fn from(error: NftError) -> Self {
+            Error::Nft(error)
+        }
+    }
+
+    impl From<PSP22Error> for Error {
+        fn from(error: PSP22Error) -> Self {
+            Error::Token(error)
+        }
+    }
+
+    impl From<PriceError> for Error {
+        fn from(error: PriceError) -> Self {
+            Error::Price(error)
+        }
+    }
+
+    impl From<CallError> for Error {
+        fn from(error: CallError) -> Self {
+            Error::Call(error)
+        }
+    }
+
+    impl From<ResolverError> for Error {
+        fn from(error: ResolverError) -> Self {
+            Error::Resolver(error)
+        }
+    }
+
+    /// Error of a batch message.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum BatchError {
+        /// The batch as a whole was rejected, e.g. the payment does not cover it.
+        Batch(Error),
+        /// The rejected items, by their index in the batch.
+        Items(Vec<(u32, Error)>),
     }
     #[ink(event)]
     pub struct Register {
         domain_name: String,
         domain_owner: AccountId,
         registration_fee: Balance,
+        /// The PSP22 token the fee was paid in, `None` for the native balance.
+        payment_token: Option<AccountId>,
+        /// The oracle price the fee was derived from, in the native balance and after
+        /// discounts. `None` for reserved names and auctions.
+        price_quote: Option<PriceQuote>,
         duration: Timestamp,
         domain_creation_time: Timestamp,
         domain_expiry_time: Timestamp,
@@ -85,21 +426,198 @@ pub mod registrar {
         resolver: AccountId,
     }
 
+    #[ink(event)]
+    pub struct Renew {
+        domain_name: String,
+        payer: AccountId,
+        duration: Timestamp,
+        renewal_fee: Balance,
+        payment_token: Option<AccountId>,
+    }
+
     #[ink(event)]
     pub struct Commit {
         commit_hash: Hash,
         caller: AccountId,
     }
+    #[ink(event)]
+    pub struct CommitmentsPruned {
+        count: u32,
+    }
+
+    #[ink(event)]
+    pub struct TldAdded {
+        tld: String,
+        min_registration_duration: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct TldRemoved {
+        tld: String,
+    }
+
+    #[ink(event)]
+    pub struct NameReserved {
+        domain_name: String,
+    }
+
+    #[ink(event)]
+    pub struct NameUnreserved {
+        domain_name: String,
+    }
+
+    #[ink(event)]
+    pub struct ReservedNameAllocated {
+        domain_name: String,
+        domain_owner: AccountId,
+        duration: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct AuctionConfigSet {
+        auction_config: Option<AuctionConfig>,
+    }
+
+    #[ink(event)]
+    pub struct AuctionStarted {
+        domain_name: String,
+        auction_id: u64,
+        bidding_ends: Timestamp,
+        reveal_ends: Timestamp,
+        reserve_price: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BidPlaced {
+        auction_id: u64,
+        bidder: AccountId,
+        deposit: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BidRevealed {
+        auction_id: u64,
+        bidder: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AuctionFinalized {
+        domain_name: String,
+        auction_id: u64,
+        winner: Option<AccountId>,
+        price: Balance,
+    }
+
     #[ink(event)]
     pub struct NftMint {
         domain_name: String,
         domain_owner: AccountId,
+        token_id: u64,
         token_uri: String,
     }
 
+    #[ink(event)]
+    pub struct FeeSplitUpdated {
+        referrer_bps: u16,
+        partner_bps: u16,
+        partner: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct ReferralPaid {
+        domain_name: String,
+        #[ink(topic)]
+        referrer: AccountId,
+        fee: Balance,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Withdrawn {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+        token: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct TreasuryWithdrawal {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+        token: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct PaymentTokenAdded {
+        #[ink(topic)]
+        token: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct PaymentTokenRemoved {
+        #[ink(topic)]
+        token: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Paused {
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Unpaused {
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct LaunchScheduleSet {
+        phases: u32,
+        public_opens_at: Option<Timestamp>,
+    }
+
+    #[ink(event)]
+    pub struct VoucherAdded {
+        #[ink(topic)]
+        voucher_hash: Hash,
+        discount: Discount,
+        max_uses: u32,
+        expires_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct VoucherRemoved {
+        #[ink(topic)]
+        voucher_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct VoucherRedeemed {
+        #[ink(topic)]
+        voucher_hash: Hash,
+        domain_name: String,
+        discount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Upgraded {
+        code_hash: Hash,
+    }
+
+    /// Length of a revenue reporting period, 30 days in milliseconds.
+    pub const REVENUE_PERIOD: Timestamp = 30 * 24 * 60 * 60 * 1000;
+
+    /// Basis points making up a whole fee.
+    pub const BPS_DENOMINATOR: u16 = 10_000;
+
     pub type Result<T> = core::result::Result<T, Error>;
+    pub type BatchResult<T> = core::result::Result<T, BatchError>;
+    /// What the PSP22 `transfer` and `transfer_from` messages answer.
+    type PSP22Result = core::result::Result<(), PSP22Error>;
 
     impl Registrar {
+        /// Creates the registrar with the default `.vne` TLD supported.
         #[ink(constructor)]
         pub fn new(
             admin: AccountId,
@@ -110,115 +628,257 @@ pub mod registrar {
             price_oracle_contract_address: AccountId,
             erc721: AccountId,
         ) -> Self {
-            Self {
-                admin,
+            let mut instance = Self {
+                access: AccessControl::new(admin, &[PAUSER, TREASURER]),
                 commitments: Mapping::default(),
                 commit_info: Mapping::default(),
                 max_commit_age,
                 min_commit_age,
-                min_registration_duration,
+                tlds: Mapping::default(),
+                supported_tlds: Vec::new(),
                 resolver_contract_address,
                 price_oracle_contract_address,
                 erc721,
                 token_id: u64::default(),
-            }
+                domain_tokens: Mapping::default(),
+                reserved_names: Mapping::default(),
+                auction_config: None,
+                auctions: Mapping::default(),
+                sealed_bids: Mapping::default(),
+                auction_count: u64::default(),
+                fee_split: FeeSplit::default(),
+                balances: Mapping::default(),
+                treasury_balance: 0,
+                revenue_tlds: Vec::new(),
+                paused: false,
+                tld_revenue: Mapping::default(),
+                period_revenue: Mapping::default(),
+                launch_schedule: Lazy::default(),
+                vouchers: Mapping::default(),
+                payment_tokens: Mapping::default(),
+                token_balances: Mapping::default(),
+                token_treasury: Mapping::default(),
+                token_revenue: Mapping::default(),
+                call_limits: Lazy::default(),
+                redeemed_leaves: Mapping::default(),
+            };
+            let tld = String::from(name::DEFAULT_TLD);
+            instance.tlds.insert(
+                tld.clone(),
+                &TldConfig {
+                    min_registration_duration,
+                },
+            );
+            instance.supported_tlds.push(tld);
+            instance
         }
 
         #[ink(message)]
         pub fn commit(&mut self, commit_hash: Hash) -> Result<()> {
-            if self.commitments.get(commit_hash).is_none() {
-                self.commitments
-                    .insert(commit_hash, &self.env().block_timestamp());
+            self.ensure_not_paused()?;
+            self.store_commitment(commit_hash)
+        }
 
-                self.env().emit_event(Commit {
-                    commit_hash,
-                    caller: Self::env().caller(),
-                });
+        /// Stores several commitments at once. Either all of them are stored
+        /// or, if any of them is rejected, none.
+        #[ink(message)]
+        pub fn commit_many(&mut self, commit_hashes: Vec<Hash>) -> BatchResult<()> {
+            self.ensure_not_paused().map_err(BatchError::Batch)?;
+            let mut errors = Vec::new();
+            for (index, commit_hash) in commit_hashes.into_iter().enumerate() {
+                if let Err(error) = self.store_commitment(commit_hash) {
+                    errors.push((index as u32, error));
+                }
+            }
 
-                return Ok(());
+            if !errors.is_empty() {
+                return Err(BatchError::Items(errors));
             }
+            Ok(())
+        }
 
-            if self
-                .commitments
-                .get(commit_hash)
-                .unwrap()
-                .add(self.max_commit_age)
-                >= self.env().block_timestamp()
-            {
-                Err(Error::UnexpiredCommitmentExists)
+        #[ink(message)]
+        pub fn commitment_status(&self, commit_hash: Hash) -> CommitmentStatus {
+            let Some(commitment) = self.commitments.get(commit_hash) else {
+                return CommitmentStatus::Unknown;
+            };
+
+            let ready_at = commitment.committed_at.saturating_add(self.min_commit_age);
+            let expires_at = commitment.committed_at.saturating_add(self.max_commit_age);
+            let current_time = self.env().block_timestamp();
+
+            if expires_at <= current_time {
+                CommitmentStatus::Expired
+            } else if ready_at > current_time {
+                CommitmentStatus::Pending { ready_at }
             } else {
-                Err(Error::UndefinedError)
+                CommitmentStatus::Ready { expires_at }
             }
         }
 
-        #[ink(message, payable)]
-        pub fn register(
-            &mut self,
-            domain_name: String,
-            domain_owner: AccountId,
-            duration: Timestamp,
-            secret: [u8; 32],
-            resolver: AccountId,
-        ) -> Result<()> {
-            assert!(
-                self.env().transferred_value()
-                    == self
-                        .read_domain_price(domain_name.clone(), duration)
-                        .unwrap(),
-                "Insufficient domain registration cost"
-            );
+        /// Removes expired commitments from storage. Anyone may call this,
+        /// unknown or still valid hashes are skipped.
+        #[ink(message)]
+        pub fn prune_commitments(&mut self, commit_hashes: Vec<Hash>) -> Result<u32> {
+            self.ensure_not_paused()?;
+            let mut count: u32 = 0;
+            for commit_hash in commit_hashes {
+                if let Some(commitment) = self.commitments.get(commit_hash) {
+                    if self.is_commitment_expired(&commitment) {
+                        self.commitments.remove(commit_hash);
+                        count = count.saturating_add(1);
+                    }
+                }
+            }
 
-            let commit_hash = self.make_commitment(
-                domain_name.clone(),
-                domain_owner,
-                duration,
-                secret,
-                resolver,
-            );
-            self.consume_commitment(domain_name.clone(), duration, commit_hash)?;
+            if count > 0 {
+                self.env().emit_event(CommitmentsPruned { count });
+            }
+            Ok(count)
+        }
 
-            // to create label hash
-            let mut label = <Sha2x256 as HashOutput>::Type::default();
-            let domain_expiry_time = self.env().block_timestamp().add(duration);
-            let domain_info: DomainInfo =
-                self.create_domain_info(domain_name.clone(), domain_owner, domain_expiry_time);
-            ink::env::hash_encoded::<Sha2x256, _>(&domain_info, &mut label);
-            let label_hash = Hash::from(label);
+        /// Registers a committed name and writes the request's records, if any, to the
+        /// resolver. A price above `max_price` fails the call, which leaves the
+        /// commitment in place to register again until it expires.
+        #[ink(message, payable)]
+        pub fn register(&mut self, request: RegistrationRequest) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.register_request(request)
+        }
 
-            let set_record = self.set_record(
-                label_hash,
-                domain_name.clone(),
-                domain_owner,
-                duration,
-                secret,
-                resolver,
-                domain_expiry_time,
-            );
+        /// Registers every requested domain or, if any request is rejected,
+        /// none of them. The payment has to cover the sum of all prices.
+        #[ink(message, payable)]
+        pub fn register_many(&mut self, mut requests: Vec<RegistrationRequest>) -> BatchResult<()> {
+            self.ensure_not_paused().map_err(BatchError::Batch)?;
+            let mut errors = Vec::new();
+            let mut price_quotes = Vec::new();
+            let mut total_price: Balance = 0;
 
-            if !set_record {
-                return Err(Error::AlreadyRegistered);
+            for (index, request) in requests.iter_mut().enumerate() {
+                let max_price = request.max_price;
+                let prepared = self.prepare_registration(request).and_then(|price_quote| {
+                    check_max_price(max_price, price_quote.total)?;
+                    total_price
+                        .checked_add(price_quote.total)
+                        .map(|total| (price_quote, total))
+                        .ok_or(Error::PriceUnavailable)
+                });
+                match prepared {
+                    Ok((price_quote, total)) => {
+                        price_quotes.push(price_quote);
+                        total_price = total;
+                    }
+                    Err(error) => errors.push((index as u32, error)),
+                }
             }
 
-            self.env()
-                .transfer(
-                    self.read_admin(),
-                    self.read_domain_price(domain_name.clone(), duration)
-                        .unwrap(),
-                )
-                .unwrap();
+            if !errors.is_empty() {
+                return Err(BatchError::Items(errors));
+            }
+            self.check_payment(total_price).map_err(BatchError::Batch)?;
 
-            self.env().emit_event(Register {
+            // a name requested twice is rejected here by the resolver
+            for (index, (request, price_quote)) in
+                requests.into_iter().zip(price_quotes).enumerate()
+            {
+                self.write_registration(request, Some(price_quote), price_quote.total, None)
+                    .map_err(|error| BatchError::Items(vec![(index as u32, error)]))?;
+            }
+
+            self.refund_excess(total_price).map_err(BatchError::Batch)
+        }
+
+        /// Like `register`, but charges the price in `token`, converted with the
+        /// oracle's rate. The caller has to approve the registrar for the amount first.
+        #[ink(message)]
+        pub fn register_with_token(
+            &mut self,
+            mut request: RegistrationRequest,
+            token: AccountId,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            let price_quote = self.prepare_registration(&mut request)?;
+            let amount = self.token_price(token, price_quote.total)?;
+            check_max_price(request.max_price, amount)?;
+            self.collect_token(token, amount)?;
+
+            self.write_registration(request, Some(price_quote), amount, Some(token))
+        }
+
+        /// Extends the registration of `domain_name` by `duration`. Anyone may pay
+        /// for a renewal, the domain keeps its owner.
+        #[ink(message, payable)]
+        pub fn renew(&mut self, domain_name: String, duration: Timestamp) -> Result<()> {
+            self.ensure_not_paused()?;
+            let mut request = RenewalRequest {
                 domain_name,
-                domain_owner,
-                registration_fee: self.env().transferred_value(),
                 duration,
-                domain_creation_time: self.env().block_timestamp(),
-                domain_expiry_time,
-                domain_grace_period: self.read_grace_period(),
-                resolver,
-            });
+            };
+            let price = self.prepare_renewal(&mut request)?;
+            self.check_payment(price)?;
 
-            Ok(())
+            self.write_renewal(request, price, None)?;
+
+            self.refund_excess(price)
+        }
+
+        /// Like `renew`, but charges the price in `token`, see `register_with_token`.
+        #[ink(message)]
+        pub fn renew_with_token(
+            &mut self,
+            domain_name: String,
+            duration: Timestamp,
+            token: AccountId,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            let mut request = RenewalRequest {
+                domain_name,
+                duration,
+            };
+            let price = self.prepare_renewal(&mut request)?;
+            let amount = self.token_price(token, price)?;
+            self.collect_token(token, amount)?;
+
+            self.write_renewal(request, amount, Some(token))
+        }
+
+        /// Renews every requested domain or, if any renewal is rejected,
+        /// none of them. The payment has to cover the sum of all prices.
+        #[ink(message, payable)]
+        pub fn renew_many(&mut self, mut requests: Vec<RenewalRequest>) -> BatchResult<()> {
+            self.ensure_not_paused().map_err(BatchError::Batch)?;
+            let mut errors = Vec::new();
+            let mut prices = Vec::new();
+            let mut total_price: Balance = 0;
+
+            for (index, request) in requests.iter_mut().enumerate() {
+                let prepared = self.prepare_renewal(request).and_then(|price| {
+                    total_price
+                        .checked_add(price)
+                        .map(|total| (price, total))
+                        .ok_or(Error::PriceUnavailable)
+                });
+                match prepared {
+                    Ok((price, total)) => {
+                        prices.push(price);
+                        total_price = total;
+                    }
+                    Err(error) => errors.push((index as u32, error)),
+                }
+            }
+
+            if !errors.is_empty() {
+                return Err(BatchError::Items(errors));
+            }
+            self.check_payment(total_price).map_err(BatchError::Batch)?;
+
+            for (index, (request, price)) in requests.into_iter().zip(prices).enumerate() {
+                self.write_renewal(request, price, None)
+                    .map_err(|error| BatchError::Items(vec![(index as u32, error)]))?;
+            }
+
+            self.refund_excess(total_price).map_err(BatchError::Batch)
         }
 
         #[ink(message)]
@@ -227,284 +887,3771 @@ pub mod registrar {
             parent_domain: String,
             sub_domain: String,
         ) -> Result<()> {
-            build_call::<MyEnvironment>()
-                .call(AccountId::from(self.resolver_contract_address))
-                .call_v1()
-                .gas_limit(0)
-                .transferred_value(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("register_subdomain")))
-                        .push_arg(parent_domain)
-                        .push_arg(sub_domain),
-                )
-                .returns::<bool>()
-                .invoke();
+            self.ensure_not_paused()?;
+            let mut resolver = self.resolver();
+            Ok(call::invoke(
+                resolver
+                    .call_mut()
+                    .register_subdomain(parent_domain, sub_domain),
+                self.read_call_limits(),
+            )??)
+        }
+
+        /// Holds `domain_name` back from public registration.
+        #[ink(message)]
+        pub fn reserve_name(&mut self, domain_name: String) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            let (domain_name, _) = self.normalize_name(&domain_name)?;
+            self.reserved_names.insert(domain_name.clone(), &());
+            self.env().emit_event(NameReserved { domain_name });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unreserve_name(&mut self, domain_name: String) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            let domain_name = name::fold(&domain_name);
+            if self.reserved_names.take(domain_name.clone()).is_none() {
+                return Err(Error::NameNotReserved);
+            }
+            self.env().emit_event(NameUnreserved { domain_name });
             Ok(())
         }
 
+        /// Registers a reserved name to `domain_owner` without commitment or payment.
+        /// The name stays reserved, so it cannot be taken publicly once it expires.
         #[ink(message)]
-        pub fn mint_nft(
+        pub fn allocate_reserved(
             &mut self,
             domain_name: String,
             domain_owner: AccountId,
-            token_uri: String,
+            duration: Timestamp,
         ) -> Result<()> {
-            let token_id = self.token_id.add(1);
-
-            let _ = build_call::<MyEnvironment>()
-                .call(AccountId::from(self.erc721))
-                .call_v1()
-                .gas_limit(0)
-                .transferred_value(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("mint")))
-                        .push_arg(token_id)
-                        .push_arg(domain_name.clone())
-                        .push_arg(domain_owner)
-                        .push_arg(token_uri.clone()),
-                )
-                .returns::<Result<()>>()
-                .invoke();
+            self.ensure_role(ADMIN)?;
+            let (domain_name, _) = self.normalize_name(&domain_name)?;
+            if !self.reserved_names.contains(domain_name.clone()) {
+                return Err(Error::NameNotReserved);
+            }
+            if !self.check_domain_availablility(domain_name.clone())? {
+                return Err(Error::AlreadyRegistered);
+            }
 
-            self.env().emit_event(NftMint {
+            let request = RegistrationRequest {
+                domain_name: domain_name.clone(),
+                domain_owner,
+                duration,
+                secret: [0x0; 32],
+                resolver: self.resolver_contract_address,
+                token_uri: None,
+                referrer: None,
+                allowlist_proof: None,
+                voucher: None,
+                max_price: None,
+                records: Vec::new(),
+            };
+            self.write_registration(request, None, 0, None)?;
+
+            self.env().emit_event(ReservedNameAllocated {
                 domain_name,
                 domain_owner,
-                token_uri,
+                duration,
             });
             Ok(())
         }
 
         #[ink(message)]
-        pub fn set_max_commit_age(&mut self, max_commit_age: u64) {
-            self.only_admin();
-            self.max_commit_age = max_commit_age;
+        pub fn is_reserved(&self, domain_name: String) -> bool {
+            self.reserved_names.contains(name::fold(&domain_name))
         }
 
+        /// Configures premium name auctions, `None` disables starting new ones.
         #[ink(message)]
-        pub fn set_min_commit_age(&mut self, min_commit_age: u64) {
-            self.only_admin();
-            self.min_commit_age = min_commit_age;
+        pub fn set_auction_config(&mut self, auction_config: Option<AuctionConfig>) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.auction_config = auction_config.clone();
+            self.env().emit_event(AuctionConfigSet { auction_config });
+            Ok(())
         }
 
+        /// Starts the auction of an available premium name. Anyone may start one.
         #[ink(message)]
-        pub fn set_min_registration_duration(&mut self, min_registration_duration: u64) {
-            self.only_admin();
-            self.min_registration_duration = min_registration_duration;
+        pub fn start_auction(&mut self, domain_name: String) -> Result<()> {
+            self.ensure_not_paused()?;
+            let config = self.auction_config.clone().ok_or(Error::AuctionsDisabled)?;
+            let (domain_name, _) = self.normalize_name(&domain_name)?;
+            if self.auctions.contains(domain_name.clone()) {
+                return Err(Error::NameInAuction);
+            }
+            if self.reserved_names.contains(domain_name.clone()) {
+                return Err(Error::NameReserved);
+            }
+            if !self.is_premium_name(domain_name.clone())? {
+                return Err(Error::NotPremiumName);
+            }
+            if !self.check_domain_availablility(domain_name.clone())? {
+                return Err(Error::AlreadyRegistered);
+            }
+            let reserve_price = self
+                .quote_domain_price(domain_name.clone(), config.registration_duration)?
+                .total;
+
+            let id = self.auction_count;
+            self.auction_count = id.checked_add(1).ok_or(Error::UndefinedError)?;
+            let bidding_ends = self
+                .env()
+                .block_timestamp()
+                .saturating_add(config.bidding_period);
+            let reveal_ends = bidding_ends.saturating_add(config.reveal_period);
+            self.auctions.insert(
+                domain_name.clone(),
+                &Auction {
+                    id,
+                    bidding_ends,
+                    reveal_ends,
+                    registration_duration: config.registration_duration,
+                    reserve_price,
+                    highest_bidder: None,
+                    highest_bid: 0,
+                    highest_deposit: 0,
+                    second_bid: reserve_price,
+                },
+            );
+
+            self.env().emit_event(AuctionStarted {
+                domain_name,
+                auction_id: id,
+                bidding_ends,
+                reveal_ends,
+                reserve_price,
+            });
+            Ok(())
+        }
+
+        /// Places a sealed bid, see `make_bid_hash`. The transferred value is the
+        /// deposit and has to cover the bid, it hides the bid only as far as it exceeds it.
+        #[ink(message, payable)]
+        pub fn bid(&mut self, domain_name: String, bid_hash: Hash) -> Result<()> {
+            self.ensure_not_paused()?;
+            let auction = self
+                .read_auction(domain_name)
+                .ok_or(Error::AuctionNotFound)?;
+            if self.env().block_timestamp() >= auction.bidding_ends {
+                return Err(Error::BiddingClosed);
+            }
+            let bidder = self.env().caller();
+            if self.sealed_bids.contains((auction.id, bidder)) {
+                return Err(Error::AlreadyBid);
+            }
+
+            let deposit = self.env().transferred_value();
+            self.sealed_bids.insert(
+                (auction.id, bidder),
+                &SealedBid {
+                    bid_hash,
+                    deposit,
+                    reveal_ends: auction.reveal_ends,
+                },
+            );
+
+            self.env().emit_event(BidPlaced {
+                auction_id: auction.id,
+                bidder,
+                deposit,
+            });
+            Ok(())
         }
 
+        /// Reveals a sealed bid. Bids that lose, are below the reserve price or exceed
+        /// their deposit are refunded right away.
         #[ink(message)]
-        pub fn make_commitment(
-            &self,
+        pub fn reveal_bid(
+            &mut self,
             domain_name: String,
-            domain_owner: AccountId,
-            duration: Timestamp,
-            secret: [u8; 32],
-            resolver: AccountId,
-        ) -> Hash {
-            assert!(
-                duration > self.min_registration_duration,
-                "duration should me more than minimum registration duration"
-            );
-            assert!(
-                self.contains_vne(domain_name.as_bytes()),
-                "Not a valid domain"
-            );
+            value: Balance,
+            salt: [u8; 32],
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            let (domain_name, _) = self.normalize_name(&domain_name)?;
+            let mut auction = self
+                .auctions
+                .get(domain_name.clone())
+                .ok_or(Error::AuctionNotFound)?;
+            let current_time = self.env().block_timestamp();
+            if current_time < auction.bidding_ends || current_time >= auction.reveal_ends {
+                return Err(Error::NotInRevealPhase);
+            }
+            let bidder = self.env().caller();
+            let sealed_bid = self
+                .sealed_bids
+                .get((auction.id, bidder))
+                .ok_or(Error::BidNotFound)?;
+            if self.make_bid_hash(domain_name.clone(), value, salt, bidder)? != sealed_bid.bid_hash
+            {
+                return Err(Error::InvalidReveal);
+            }
+            self.sealed_bids.remove((auction.id, bidder));
 
-            let mut commit = <Sha2x256 as HashOutput>::Type::default();
-            let commit_info =
-                self.create_commit_info(domain_name, domain_owner, duration, secret, resolver);
+            let mut refund = sealed_bid.deposit;
+            if value <= sealed_bid.deposit && value >= auction.reserve_price {
+                if value > auction.highest_bid {
+                    if let Some(previous_bidder) = auction.highest_bidder {
+                        self.refund_bid(previous_bidder, auction.highest_deposit)?;
+                        auction.second_bid = auction.highest_bid;
+                    }
+                    auction.highest_bidder = Some(bidder);
+                    auction.highest_bid = value;
+                    auction.highest_deposit = sealed_bid.deposit;
+                    refund = 0;
+                } else if value > auction.second_bid {
+                    auction.second_bid = value;
+                }
+            }
+            self.auctions.insert(domain_name, &auction);
 
-            ink::env::hash_encoded::<Sha2x256, _>(&commit_info, &mut commit);
-            Hash::from(commit)
+            self.refund_bid(bidder, refund)?;
+            self.env().emit_event(BidRevealed {
+                auction_id: auction.id,
+                bidder,
+                value,
+            });
+            Ok(())
         }
 
+        /// Ends an auction once its reveal phase is over. The highest bidder gets the
+        /// domain for the second highest price and the rest of the deposit back. If the
+        /// name was registered in the meantime, the whole deposit is refunded instead.
         #[ink(message)]
-        pub fn check_domain_availablility(&self, domain_name: String) -> bool {
-            build_call::<MyEnvironment>()
-                .call(AccountId::from(self.resolver_contract_address))
-                .call_v1()
-                .gas_limit(0)
-                .transferred_value(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
-                        "check_domain_availablility"
-                    )))
-                    .push_arg(domain_name),
-                )
-                .returns::<bool>()
-                .invoke()
+        pub fn finalize_auction(&mut self, domain_name: String) -> Result<()> {
+            self.ensure_not_paused()?;
+            let (domain_name, _) = self.normalize_name(&domain_name)?;
+            let auction = self
+                .auctions
+                .get(domain_name.clone())
+                .ok_or(Error::AuctionNotFound)?;
+            if self.env().block_timestamp() < auction.reveal_ends {
+                return Err(Error::AuctionNotEnded);
+            }
+            self.auctions.remove(domain_name.clone());
+
+            let mut winner = None;
+            let mut price = 0;
+            if let Some(bidder) = auction.highest_bidder {
+                // a taken name would fail every attempt and lock the deposit for good
+                if self.check_domain_availablility(domain_name.clone())? {
+                    price = auction.second_bid;
+                    winner = Some(bidder);
+                }
+            }
+            if let Some(winner) = winner {
+                let request = RegistrationRequest {
+                    domain_name: domain_name.clone(),
+                    domain_owner: winner,
+                    duration: auction.registration_duration,
+                    secret: [0x0; 32],
+                    resolver: self.resolver_contract_address,
+                    token_uri: None,
+                    referrer: None,
+                    allowlist_proof: None,
+                    voucher: None,
+                    max_price: None,
+                    records: Vec::new(),
+                };
+                self.write_registration(request, None, price, None)?;
+            }
+            if let Some(bidder) = auction.highest_bidder {
+                self.refund_bid(bidder, auction.highest_deposit.saturating_sub(price))?;
+            }
+
+            self.env().emit_event(AuctionFinalized {
+                domain_name,
+                auction_id: auction.id,
+                winner,
+                price,
+            });
+            Ok(())
         }
 
+        /// Returns the deposit of a bid that was not revealed in time.
         #[ink(message)]
-        pub fn read_domain_price(
+        pub fn reclaim_bid(&mut self, auction_id: u64) -> Result<()> {
+            self.ensure_not_paused()?;
+            let bidder = self.env().caller();
+            let sealed_bid = self
+                .sealed_bids
+                .get((auction_id, bidder))
+                .ok_or(Error::BidNotFound)?;
+            if self.env().block_timestamp() < sealed_bid.reveal_ends {
+                return Err(Error::AuctionNotEnded);
+            }
+
+            self.sealed_bids.remove((auction_id, bidder));
+            self.env()
+                .transfer(bidder, sealed_bid.deposit)
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        /// Computes the hash to pass to `bid` for bidding `value` on `domain_name`.
+        #[ink(message)]
+        pub fn make_bid_hash(
             &self,
             domain_name: String,
-            duration: Timestamp,
-        ) -> Option<Balance> {
-            build_call::<MyEnvironment>()
-                .call(AccountId::from(self.price_oracle_contract_address))
-                .call_v1()
-                .gas_limit(0)
-                .transferred_value(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("calculate_price")))
-                        .push_arg(domain_name)
-                        .push_arg(duration),
-                )
-                .returns::<Option<Balance>>()
-                .invoke()
+            value: Balance,
+            salt: [u8; 32],
+            bidder: AccountId,
+        ) -> Result<Hash> {
+            let (domain_name, _) = self.normalize_name(&domain_name)?;
+            let mut bid_hash = <Sha2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Sha2x256, _>(
+                &(domain_name, value, salt, bidder),
+                &mut bid_hash,
+            );
+            Ok(Hash::from(bid_hash))
         }
 
         #[ink(message)]
-        pub fn read_admin(&self) -> AccountId {
-            self.admin
+        pub fn read_auction(&self, domain_name: String) -> Option<Auction> {
+            self.auctions.get(name::fold(&domain_name))
         }
 
         #[ink(message)]
-        pub fn read_resolver(&self) -> AccountId {
-            self.resolver_contract_address
+        pub fn read_auction_config(&self) -> Option<AuctionConfig> {
+            self.auction_config.clone()
         }
 
+        /// Mints the NFT of a domain that was registered without one, to the domain's
+        /// owner in the resolver.
         #[ink(message)]
-        pub fn read_max_commit_age(&self) -> Timestamp {
-            self.max_commit_age
+        pub fn mint_nft(&mut self, domain_name: String, token_uri: String) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            let (domain_name, _) = self.normalize_name(&domain_name)?;
+            if self.check_domain_availablility(domain_name.clone())? {
+                return Err(Error::DomainNotRegistered);
+            }
+            let resolver = self.resolver();
+            let domain_owner = call::invoke(
+                resolver.call().read_domain_owner(domain_name.clone()),
+                self.read_call_limits(),
+            )??;
+            self.mint_domain_nft(domain_name, domain_owner, token_uri)
         }
+
+        /// Sets how fees are split, the referrer and partner shares may not exceed the whole fee.
         #[ink(message)]
-        pub fn read_min_commit_age(&self) -> Timestamp {
-            self.min_commit_age
+        pub fn set_fee_split(&mut self, fee_split: FeeSplit) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            if fee_split.referrer_bps.saturating_add(fee_split.partner_bps) > BPS_DENOMINATOR {
+                return Err(Error::InvalidFeeSplit);
+            }
+            self.fee_split = fee_split.clone();
+
+            self.env().emit_event(FeeSplitUpdated {
+                referrer_bps: fee_split.referrer_bps,
+                partner_bps: fee_split.partner_bps,
+                partner: fee_split.partner,
+            });
+            Ok(())
         }
+
         #[ink(message)]
-        pub fn read_min_registration_duration(&self) -> Timestamp {
-            self.min_registration_duration
+        pub fn read_fee_split(&self) -> FeeSplit {
+            self.fee_split.clone()
         }
 
+        /// Pays out the fees accrued to the caller.
         #[ink(message)]
-        pub fn read_grace_period(&self) -> Timestamp {
-            build_call::<MyEnvironment>()
-            .call(AccountId::from(self.resolver_contract_address))
-            .call_v1()
-            .gas_limit(0)
-            .transferred_value(0)
-            .exec_input(
-                ExecutionInput::new(Selector::new(ink::selector_bytes!(
-                    "read_grace_period"
-                )))
-            )
-            .returns::<Timestamp>()
-            .invoke()
+        pub fn withdraw(&mut self) -> Result<()> {
+            self.ensure_not_paused()?;
+            let account = self.env().caller();
+            let amount = self.balances.take(account).unwrap_or_default();
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+
+            self.env()
+                .transfer(account, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            self.env().emit_event(Withdrawn {
+                account,
+                amount,
+                token: None,
+            });
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn current_timestamp(&self) -> Timestamp {
-            self.env().block_timestamp()
+        pub fn read_balance(&self, account: AccountId) -> Balance {
+            self.balances.get(account).unwrap_or_default()
         }
 
-        fn only_admin(&self) {
-            let caller = Self::env().caller();
-            assert_eq!(caller, self.admin, "Not the contract owner");
+        /// Halts commits, registrations, renewals, auctions and withdrawals until `unpause`.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.ensure_role(PAUSER)?;
+            self.paused = true;
+            self.env().emit_event(Paused {
+                account: self.env().caller(),
+            });
+            Ok(())
         }
 
-        fn consume_commitment(
-            &self,
-            _domain_name: String,
-            duration: u64,
-            commit_hash: Hash,
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.ensure_role(PAUSER)?;
+            self.paused = false;
+            self.env().emit_event(Unpaused {
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Limits registration to allowlists until `public_opens_at`, `None` opens it
+        /// to everyone right away. Registration is closed before the first phase.
+        #[ink(message)]
+        pub fn set_launch_schedule(
+            &mut self,
+            launch_schedule: Option<LaunchSchedule>,
         ) -> Result<()> {
-            let current_time = self.env().block_timestamp();
-            let commitment = self
-                .commitments
-                .get(commit_hash)
-                .ok_or(Error::CommitmentNotFound)?;
+            self.ensure_role(ADMIN)?;
+            let Some(launch_schedule) = launch_schedule else {
+                self.launch_schedule.set(&LaunchSchedule {
+                    phases: Vec::new(),
+                    public_opens_at: 0,
+                });
+                self.env().emit_event(LaunchScheduleSet {
+                    phases: 0,
+                    public_opens_at: None,
+                });
+                return Ok(());
+            };
 
-            if commitment.add(self.min_commit_age) > current_time {
-                return Err(Error::CommitmentTooNew);
-            }
-            if commitment.add(self.max_commit_age) <= current_time {
-                return Err(Error::CommitmentTooOld);
+            let mut starts_at = None;
+            for phase in &launch_schedule.phases {
+                if starts_at.is_some_and(|previous| phase.starts_at <= previous)
+                    || phase.starts_at >= launch_schedule.public_opens_at
+                    || phase.discount_bps > BPS_DENOMINATOR
+                {
+                    return Err(Error::InvalidSchedule);
+                }
+                starts_at = Some(phase.starts_at);
             }
-            if duration < self.min_registration_duration {
-                return Err(Error::DurationTooShort);
+
+            self.launch_schedule.set(&launch_schedule);
+            self.env().emit_event(LaunchScheduleSet {
+                phases: launch_schedule.phases.len() as u32,
+                public_opens_at: Some(launch_schedule.public_opens_at),
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn read_launch_schedule(&self) -> Option<LaunchSchedule> {
+            self.launch_schedule
+                .get()
+                .filter(|schedule| schedule.public_opens_at > 0)
+        }
+
+        #[ink(message)]
+        pub fn registration_phase(&self) -> RegistrationPhase {
+            let Some(schedule) = self.launch_schedule.get() else {
+                return RegistrationPhase::Public;
+            };
+            let current_time = self.env().block_timestamp();
+            if current_time >= schedule.public_opens_at {
+                return RegistrationPhase::Public;
             }
-            if !self.check_domain_availablility(_domain_name) {
-                return Err(Error::AlreadyRegistered);
+
+            match schedule
+                .phases
+                .iter()
+                .rposition(|phase| phase.starts_at <= current_time)
+            {
+                Some(index) => RegistrationPhase::Allowlist {
+                    index: index as u32,
+                },
+                None => RegistrationPhase::Closed,
             }
-            Ok(())
         }
 
-        fn create_commit_info(
+        /// Computes the allowlist leaf of `account`, restricted to `domain_name` if set.
+        #[ink(message)]
+        pub fn make_allowlist_leaf(
             &self,
-            domain_name: String,
-            domain_owner: AccountId,
-            duration: Timestamp,
-            secret: [u8; 32],
-            resolver: AccountId,
-        ) -> CommitInfo {
-            CommitInfo {
-                domain_name,
-                domain_owner,
-                duration,
-                secret,
-                resolver,
+            account: AccountId,
+            domain_name: Option<String>,
+        ) -> Result<Hash> {
+            let domain_name = match domain_name {
+                Some(domain_name) => Some(self.normalize_name(&domain_name)?.0),
+                None => None,
+            };
+            Ok(self.allowlist_leaf(account, domain_name))
+        }
+
+        /// Publishes a voucher under the hash of its code, see `make_voucher_hash`.
+        /// Replacing a voucher resets its redemptions.
+        #[ink(message)]
+        pub fn add_voucher(
+            &mut self,
+            voucher_hash: Hash,
+            discount: Discount,
+            max_uses: u32,
+            expires_at: Timestamp,
+        ) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            if matches!(discount, Discount::Percent { bps } if bps == 0 || bps > BPS_DENOMINATOR)
+                || matches!(discount, Discount::Fixed { amount: 0 })
+                || max_uses == 0
+            {
+                return Err(Error::InvalidDiscount);
             }
+
+            self.vouchers.insert(
+                voucher_hash,
+                &Voucher {
+                    discount,
+                    max_uses,
+                    redeemed: 0,
+                    expires_at,
+                },
+            );
+            self.env().emit_event(VoucherAdded {
+                voucher_hash,
+                discount,
+                max_uses,
+                expires_at,
+            });
+            Ok(())
         }
 
-        fn create_domain_info(
-            &self,
-            domain_name: String,
-            domain_owner: AccountId,
-            domain_expiry_time: Timestamp,
-        ) -> DomainInfo {
-            DomainInfo {
-                domain_name,
-                domain_owner,
-                domain_expiry_time,
+        /// Withdraws a voucher, returns `false` if there was none.
+        #[ink(message)]
+        pub fn remove_voucher(&mut self, voucher_hash: Hash) -> Result<bool> {
+            self.ensure_role(ADMIN)?;
+            if self.vouchers.take(voucher_hash).is_none() {
+                return Ok(false);
             }
+            self.env().emit_event(VoucherRemoved { voucher_hash });
+            Ok(true)
         }
 
-        fn contains_vne(&self, input: &[u8]) -> bool {
-            let substring: &[u8] = b".vne";
-            let input_len = input.len();
-            let substring_len = substring.len();
+        #[ink(message)]
+        pub fn read_voucher(&self, voucher_hash: Hash) -> Option<Voucher> {
+            self.vouchers.get(voucher_hash)
+        }
 
-            if input_len < substring_len {
-                return false;
-            }
+        #[ink(message)]
+        pub fn make_voucher_hash(&self, code: String) -> Hash {
+            let mut voucher_hash = <Sha2x256 as HashOutput>::Type::default();
+            ink::env::hash_bytes::<Sha2x256>(code.as_bytes(), &mut voucher_hash);
+            Hash::from(voucher_hash)
+        }
 
-            for i in 0..=(input_len.checked_sub(substring_len)).unwrap() {
-                if &input[i..i.add(substring_len)] == substring {
-                    return true;
-                }
-            }
-            false
+        /// Sends `amount` of the treasury to `to`. Only treasurers may call this.
+        #[ink(message)]
+        pub fn withdraw_treasury(&mut self, amount: Balance, to: AccountId) -> Result<()> {
+            self.ensure_role(TREASURER)?;
+            self.treasury_balance = self
+                .treasury_balance
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientTreasury)?;
+
+            self.env()
+                .transfer(to, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            self.env().emit_event(TreasuryWithdrawal {
+                to,
+                amount,
+                token: None,
+            });
+            Ok(())
         }
 
-        fn set_record(
+        #[ink(message)]
+        pub fn read_treasury_balance(&self) -> Balance {
+            self.treasury_balance
+        }
+
+        /// Accepts `token` for `register_with_token` and `renew_with_token`. The price
+        /// oracle has to know its rate.
+        #[ink(message)]
+        pub fn add_payment_token(&mut self, token: AccountId) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.payment_tokens.insert(token, &());
+            self.env().emit_event(PaymentTokenAdded { token });
+            Ok(())
+        }
+
+        /// Stops accepting `token`, returns `false` if it was not accepted. Balances
+        /// already held in it can still be withdrawn.
+        #[ink(message)]
+        pub fn remove_payment_token(&mut self, token: AccountId) -> Result<bool> {
+            self.ensure_role(ADMIN)?;
+            if self.payment_tokens.take(token).is_none() {
+                return Ok(false);
+            }
+            self.env().emit_event(PaymentTokenRemoved { token });
+            Ok(true)
+        }
+
+        #[ink(message)]
+        pub fn is_payment_token(&self, token: AccountId) -> bool {
+            self.payment_tokens.contains(token)
+        }
+
+        /// Pays the caller everything credited to them in `token`.
+        #[ink(message)]
+        pub fn withdraw_token(&mut self, token: AccountId) -> Result<()> {
+            self.ensure_not_paused()?;
+            let account = self.env().caller();
+            let amount = self
+                .token_balances
+                .take((token, account))
+                .unwrap_or_default();
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+
+            self.send_token(token, account, amount)?;
+            self.env().emit_event(Withdrawn {
+                account,
+                amount,
+                token: Some(token),
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn read_token_balance(&self, token: AccountId, account: AccountId) -> Balance {
+            self.token_balances
+                .get((token, account))
+                .unwrap_or_default()
+        }
+
+        /// Sends `amount` of the treasury held in `token` to `to`. Only treasurers may call this.
+        #[ink(message)]
+        pub fn withdraw_treasury_token(
+            &mut self,
+            token: AccountId,
+            amount: Balance,
+            to: AccountId,
+        ) -> Result<()> {
+            self.ensure_role(TREASURER)?;
+            let treasury = self
+                .read_token_treasury(token)
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientTreasury)?;
+            self.token_treasury.insert(token, &treasury);
+
+            self.send_token(token, to, amount)?;
+            self.env().emit_event(TreasuryWithdrawal {
+                to,
+                amount,
+                token: Some(token),
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn read_token_treasury(&self, token: AccountId) -> Balance {
+            self.token_treasury.get(token).unwrap_or_default()
+        }
+
+        /// Fees paid in `token` since deployment, across all TLDs.
+        #[ink(message)]
+        pub fn read_token_revenue(&self, token: AccountId) -> Revenue {
+            self.token_revenue.get(token).unwrap_or_default()
+        }
+
+        /// Revenue of every TLD that has earned fees, since deployment.
+        #[ink(message)]
+        pub fn revenue_report(&self) -> Vec<TldRevenue> {
+            self.revenue_tlds
+                .iter()
+                .map(|tld| TldRevenue {
+                    tld: tld.clone(),
+                    revenue: self.tld_revenue.get(tld).unwrap_or_default(),
+                })
+                .collect()
+        }
+
+        /// Revenue of every TLD that has earned fees, in the given period,
+        /// see `current_revenue_period`.
+        #[ink(message)]
+        pub fn revenue_report_for_period(&self, period: u64) -> Vec<TldRevenue> {
+            self.revenue_tlds
+                .iter()
+                .map(|tld| TldRevenue {
+                    tld: tld.clone(),
+                    revenue: self
+                        .period_revenue
+                        .get((tld.clone(), period))
+                        .unwrap_or_default(),
+                })
+                .collect()
+        }
+
+        /// Index of the current revenue period, periods are `REVENUE_PERIOD` long.
+        #[ink(message)]
+        pub fn current_revenue_period(&self) -> u64 {
+            self.env().block_timestamp() / REVENUE_PERIOD
+        }
+
+        #[ink(message)]
+        pub fn read_domain_token_id(&self, domain_name: String) -> Option<u64> {
+            self.domain_tokens.get(name::fold(&domain_name))
+        }
+
+        #[ink(message)]
+        pub fn read_last_token_id(&self) -> u64 {
+            self.token_id
+        }
+
+        #[ink(message)]
+        pub fn set_max_commit_age(&mut self, max_commit_age: u64) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.max_commit_age = max_commit_age;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_min_commit_age(&mut self, min_commit_age: u64) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.min_commit_age = min_commit_age;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn add_tld(&mut self, tld: String, min_registration_duration: Timestamp) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            let tld =
+                name::normalize_label(&tld).map_err(|reason| Error::InvalidName { reason })?;
+            if self.tlds.contains(tld.clone()) {
+                return Err(Error::TldAlreadySupported);
+            }
+
+            self.tlds.insert(
+                tld.clone(),
+                &TldConfig {
+                    min_registration_duration,
+                },
+            );
+            self.supported_tlds.push(tld.clone());
+            self.env().emit_event(TldAdded {
+                tld,
+                min_registration_duration,
+            });
+            Ok(())
+        }
+
+        /// Stops registrations under `tld`. Existing domains are not affected.
+        #[ink(message)]
+        pub fn remove_tld(&mut self, tld: String) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            let tld = name::fold(&tld);
+            if !self.tlds.contains(tld.clone()) {
+                return Err(Error::UnsupportedTld);
+            }
+
+            self.tlds.remove(tld.clone());
+            self.supported_tlds.retain(|supported| *supported != tld);
+            self.env().emit_event(TldRemoved { tld });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_min_registration_duration(
+            &mut self,
+            tld: String,
+            min_registration_duration: Timestamp,
+        ) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            let tld = name::fold(&tld);
+            let mut config = self.tlds.get(tld.clone()).ok_or(Error::UnsupportedTld)?;
+            config.min_registration_duration = min_registration_duration;
+            self.tlds.insert(tld, &config);
+            Ok(())
+        }
+
+        /// The commitment to pass to `commit`. It is bound to the caller, so query it
+        /// as the account that commits and registers the name.
+        #[ink(message)]
+        pub fn make_commitment(
             &self,
-            label_hash: Hash,
             domain_name: String,
             domain_owner: AccountId,
             duration: Timestamp,
             secret: [u8; 32],
             resolver: AccountId,
-            domain_expiry_time: Timestamp,
-        ) -> bool {
-            build_call::<MyEnvironment>()
-                .call(AccountId::from(self.resolver_contract_address))
-                .call_v1()
-                .gas_limit(0)
-                .transferred_value(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("set_record")))
-                        .push_arg(label_hash)
-                        .push_arg(domain_name.clone())
-                        .push_arg(domain_owner)
-                        .push_arg(duration)
-                        .push_arg(secret)
-                        .push_arg(resolver)
-                        .push_arg(domain_expiry_time),
-                )
-                .returns::<bool>()
-                .invoke()
+        ) -> Result<Hash> {
+            self.commitment_hash(domain_name, domain_owner, duration, secret, resolver, &[])
+        }
+
+        /// The commitment for a request with records, which also covers the records.
+        #[ink(message)]
+        pub fn make_commitment_with_records(
+            &self,
+            domain_name: String,
+            domain_owner: AccountId,
+            duration: Timestamp,
+            secret: [u8; 32],
+            resolver: AccountId,
+            records: Vec<RecordEntry>,
+        ) -> Result<Hash> {
+            self.commitment_hash(
+                domain_name,
+                domain_owner,
+                duration,
+                secret,
+                resolver,
+                &records,
+            )
+        }
+
+        #[ink(message)]
+        pub fn check_domain_availablility(&self, domain_name: String) -> Result<bool> {
+            let resolver = self.resolver();
+            Ok(call::invoke(
+                resolver.call().check_domain_availablility(domain_name),
+                self.read_call_limits(),
+            )?)
+        }
+
+        #[ink(message)]
+        pub fn read_domain_price(
+            &self,
+            domain_name: String,
+            duration: Timestamp,
+        ) -> Option<Balance> {
+            self.quote_domain_price(domain_name, duration)
+                .map(|price_quote| price_quote.total)
+                .ok()
+        }
+
+        /// Renewals are charged the base price, without any release premium.
+        #[ink(message)]
+        pub fn read_renewal_price(
+            &self,
+            domain_name: String,
+            duration: Timestamp,
+        ) -> Option<Balance> {
+            self.quote_domain_price(domain_name, duration)
+                .map(|price_quote| price_quote.base())
+                .ok()
+        }
+
+        /// The itemized price of registering `domain_name` for `duration`, before
+        /// allowlist and voucher discounts.
+        #[ink(message)]
+        pub fn read_price_quote(
+            &self,
+            domain_name: String,
+            duration: Timestamp,
+        ) -> Result<PriceQuote> {
+            let domain_name = self.normalize_name(&domain_name)?.0;
+            self.quote_domain_price(domain_name, duration)
+        }
+
+        #[ink(message)]
+        pub fn read_admin(&self) -> AccountId {
+            self.access.admin()
+        }
+
+        /// Replaces the code of the contract, keeping its storage.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+            self.env().emit_event(Upgraded { code_hash });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn read_pending_admin(&self) -> Option<AccountId> {
+            self.access.pending_admin()
+        }
+
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+            self.access.has_role(role, account)
+        }
+
+        /// Grants `role` to `account`. Only the admin may call this.
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.grant_role(sender, role, account)?;
+            self.env().emit_event(RoleGranted {
+                account,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Revokes `role` from `account`. Only the admin may call this.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.revoke_role(sender, role, account)?;
+            self.env().emit_event(RoleRevoked {
+                account,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn renounce_role(&mut self, role: Role) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.renounce_role(sender, role)?;
+            self.env().emit_event(RoleRevoked {
+                account: sender,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Proposes the next admin, who takes over once it calls `accept_admin`.
+        #[ink(message)]
+        pub fn propose_admin(&mut self, pending_admin: AccountId) -> Result<()> {
+            self.access
+                .propose_admin(self.env().caller(), pending_admin)?;
+            self.env().emit_event(AdminTransferProposed {
+                admin: self.access.admin(),
+                pending_admin,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn accept_admin(&mut self) -> Result<()> {
+            let admin = self.env().caller();
+            let previous_admin = self.access.accept_admin(admin)?;
+            self.env().emit_event(AdminTransferred {
+                previous_admin,
+                admin,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn read_resolver(&self) -> AccountId {
+            self.resolver_contract_address
+        }
+
+        #[ink(message)]
+        pub fn read_max_commit_age(&self) -> Timestamp {
+            self.max_commit_age
+        }
+        #[ink(message)]
+        pub fn read_min_commit_age(&self) -> Timestamp {
+            self.min_commit_age
+        }
+        #[ink(message)]
+        pub fn read_min_registration_duration(&self, tld: String) -> Option<Timestamp> {
+            self.tlds
+                .get(name::fold(&tld))
+                .map(|config| config.min_registration_duration)
+        }
+
+        #[ink(message)]
+        pub fn read_supported_tlds(&self) -> Vec<String> {
+            self.supported_tlds.clone()
+        }
+
+        #[ink(message)]
+        pub fn read_grace_period(&self) -> Result<Timestamp> {
+            let resolver = self.resolver();
+            Ok(call::invoke(
+                resolver.call().read_grace_period(),
+                self.read_call_limits(),
+            )?)
+        }
+
+        /// Sets the limits of the calls to the resolver, price oracle, NFT and payment
+        /// tokens. Only the admin can set them.
+        #[ink(message)]
+        pub fn set_call_limits(&mut self, call_limits: CallLimits) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.call_limits.set(&call_limits);
+            self.env().emit_event(CallLimitsSet {
+                ref_time_limit: call_limits.ref_time_limit,
+                proof_size_limit: call_limits.proof_size_limit,
+                storage_deposit_limit: call_limits.storage_deposit_limit,
+            });
+            Ok(())
+        }
+
+        /// The limits of the calls to other contracts, zero meaning no limit.
+        #[ink(message)]
+        pub fn read_call_limits(&self) -> CallLimits {
+            self.call_limits.get().unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn current_timestamp(&self) -> Timestamp {
+            self.env().block_timestamp()
+        }
+
+        fn ensure_role(&self, role: Role) -> Result<()> {
+            Ok(self.access.ensure_role(role, self.env().caller())?)
+        }
+
+        fn ensure_not_paused(&self) -> Result<()> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
+        fn store_commitment(&mut self, commit_hash: Hash) -> Result<()> {
+            // an expired commitment may be replaced, a live one may not
+            if let Some(commitment) = self.commitments.get(commit_hash) {
+                if !self.is_commitment_expired(&commitment) {
+                    return Err(Error::UnexpiredCommitmentExists);
+                }
+            }
+
+            let caller = Self::env().caller();
+            self.commitments.insert(
+                commit_hash,
+                &Commitment {
+                    committed_at: self.env().block_timestamp(),
+                    committer: caller,
+                },
+            );
+
+            self.env().emit_event(Commit {
+                commit_hash,
+                caller,
+            });
+
+            Ok(())
+        }
+
+        fn register_request(&mut self, mut request: RegistrationRequest) -> Result<()> {
+            let price_quote = self.prepare_registration(&mut request)?;
+            let price = price_quote.total;
+            check_max_price(request.max_price, price)?;
+            self.check_payment(price)?;
+
+            self.write_registration(request, Some(price_quote), price, None)?;
+
+            self.refund_excess(price)
+        }
+
+        /// Normalizes the requested name, consumes its commitment and returns its price.
+        fn prepare_registration(
+            &mut self,
+            request: &mut RegistrationRequest,
+        ) -> Result<PriceQuote> {
+            if request.referrer == Some(self.env().caller()) {
+                return Err(Error::InvalidReferrer);
+            }
+            request.domain_name = self.normalize_name(&request.domain_name)?.0;
+            if self.auction_config.is_some() && self.is_premium_name(request.domain_name.clone())? {
+                return Err(Error::AuctionRequired);
+            }
+            let discount_bps =
+                self.redeem_allowlist(&request.domain_name, &request.allowlist_proof)?;
+
+            let commit_hash = self.commitment_hash(
+                request.domain_name.clone(),
+                request.domain_owner,
+                request.duration,
+                request.secret,
+                request.resolver,
+                &request.records,
+            )?;
+            self.consume_commitment(request.domain_name.clone(), commit_hash)?;
+
+            let mut price_quote =
+                self.quote_domain_price(request.domain_name.clone(), request.duration)?;
+            price_quote.apply_discount(bps_share(price_quote.total, discount_bps));
+            if let Some(code) = request.voucher.clone() {
+                let price = self.redeem_voucher(code, &request.domain_name, price_quote.total)?;
+                price_quote.apply_discount(price_quote.total - price);
+            }
+            Ok(price_quote)
+        }
+
+        /// Uses up one redemption of the voucher with `code` and returns the
+        /// discounted `price`.
+        fn redeem_voucher(
+            &mut self,
+            code: String,
+            domain_name: &str,
+            price: Balance,
+        ) -> Result<Balance> {
+            let voucher_hash = self.make_voucher_hash(code);
+            let mut voucher = self
+                .vouchers
+                .get(voucher_hash)
+                .ok_or(Error::VoucherNotFound)?;
+            if self.env().block_timestamp() >= voucher.expires_at {
+                return Err(Error::VoucherExpired);
+            }
+            if voucher.redeemed >= voucher.max_uses {
+                return Err(Error::VoucherExhausted);
+            }
+            voucher.redeemed += 1;
+            self.vouchers.insert(voucher_hash, &voucher);
+
+            let discount = match voucher.discount {
+                Discount::Percent { bps } => bps_share(price, bps),
+                Discount::Fixed { amount } => amount.min(price),
+            };
+            self.env().emit_event(VoucherRedeemed {
+                voucher_hash,
+                domain_name: String::from(domain_name),
+                discount,
+            });
+            Ok(price - discount)
+        }
+
+        /// Checks that the caller may register `domain_name` in the current phase,
+        /// uses up their allowlist entry and returns its discount in basis points.
+        /// Each entry registers one name, whether it names one or not.
+        fn redeem_allowlist(
+            &mut self,
+            domain_name: &str,
+            allowlist_proof: &Option<AllowlistProof>,
+        ) -> Result<u16> {
+            let index = match self.registration_phase() {
+                RegistrationPhase::Public => return Ok(0),
+                RegistrationPhase::Closed => return Err(Error::RegistrationNotOpen),
+                RegistrationPhase::Allowlist { index } => index,
+            };
+            let phase = self
+                .launch_schedule
+                .get()
+                .and_then(|schedule| schedule.phases.get(index as usize).cloned())
+                .ok_or(Error::RegistrationNotOpen)?;
+            let allowlist_proof = allowlist_proof.as_ref().ok_or(Error::ProofRequired)?;
+
+            if let Some(name) = &allowlist_proof.name {
+                if name != domain_name {
+                    return Err(Error::InvalidProof);
+                }
+            }
+            let leaf = self.allowlist_leaf(self.env().caller(), allowlist_proof.name.clone());
+            if !merkle::verify(&phase.merkle_root, leaf, &allowlist_proof.proof) {
+                return Err(Error::InvalidProof);
+            }
+            if self.redeemed_leaves.contains((phase.merkle_root, leaf)) {
+                return Err(Error::AllowlistEntryRedeemed);
+            }
+            self.redeemed_leaves.insert((phase.merkle_root, leaf), &());
+            Ok(phase.discount_bps)
+        }
+
+        fn allowlist_leaf(&self, account: AccountId, domain_name: Option<String>) -> Hash {
+            let mut leaf = <Sha2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Sha2x256, _>(&(account, domain_name), &mut leaf);
+            Hash::from(leaf)
+        }
+
+        /// Writes a prepared registration paid with `fee`, in `payment_token` if set.
+        fn write_registration(
+            &mut self,
+            request: RegistrationRequest,
+            price_quote: Option<PriceQuote>,
+            fee: Balance,
+            payment_token: Option<AccountId>,
+        ) -> Result<()> {
+            // to create label hash
+            let mut label = <Sha2x256 as HashOutput>::Type::default();
+            let domain_expiry_time = self.env().block_timestamp().add(request.duration);
+            let domain_info: DomainInfo = self.create_domain_info(
+                request.domain_name.clone(),
+                request.domain_owner,
+                domain_expiry_time,
+            );
+            ink::env::hash_encoded::<Sha2x256, _>(&domain_info, &mut label);
+            let label_hash = Hash::from(label);
+
+            self.set_record(label_hash, &request, domain_expiry_time)?;
+
+            let RegistrationRequest {
+                domain_name,
+                domain_owner,
+                duration,
+                resolver,
+                token_uri,
+                referrer,
+                records,
+                ..
+            } = request;
+
+            // a failed mint fails the message, which reverts the record as well
+            if let Some(token_uri) = token_uri {
+                self.mint_domain_nft(domain_name.clone(), domain_owner, token_uri)?;
+            }
+            if !records.is_empty() {
+                self.set_resolver_records(domain_name.clone(), records)?;
+            }
+            self.split_fee(&domain_name, fee, referrer, payment_token)?;
+
+            self.env().emit_event(Register {
+                domain_name,
+                domain_owner,
+                registration_fee: fee,
+                payment_token,
+                price_quote,
+                duration,
+                domain_creation_time: self.env().block_timestamp(),
+                domain_expiry_time,
+                domain_grace_period: self.read_grace_period()?,
+                resolver,
+            });
+
+            Ok(())
+        }
+
+        /// Normalizes the name to renew, checks that it is registered and returns the price.
+        fn prepare_renewal(&self, request: &mut RenewalRequest) -> Result<Balance> {
+            request.domain_name = self.normalize_name(&request.domain_name)?.0;
+            if self.check_domain_availablility(request.domain_name.clone())? {
+                return Err(Error::DomainNotRegistered);
+            }
+            // renewals are charged the base price, without any release premium
+            Ok(self
+                .quote_domain_price(request.domain_name.clone(), request.duration)?
+                .base())
+        }
+
+        fn write_renewal(
+            &mut self,
+            request: RenewalRequest,
+            fee: Balance,
+            payment_token: Option<AccountId>,
+        ) -> Result<()> {
+            self.renew_domain(request.domain_name.clone(), request.duration)?;
+            self.split_fee(&request.domain_name, fee, None, payment_token)?;
+
+            self.env().emit_event(Renew {
+                domain_name: request.domain_name,
+                payer: self.env().caller(),
+                duration: request.duration,
+                renewal_fee: fee,
+                payment_token,
+            });
+            Ok(())
+        }
+
+        fn mint_domain_nft(
+            &mut self,
+            domain_name: String,
+            domain_owner: AccountId,
+            token_uri: String,
+        ) -> Result<()> {
+            if self.domain_tokens.contains(domain_name.clone()) {
+                return Err(Error::NftAlreadyMinted);
+            }
+            let token_id = self.token_id.checked_add(1).ok_or(Error::MintFailed)?;
+
+            let mut nft: contract_ref!(VnsNft, MyEnvironment) =
+                FromAccountId::from_account_id(self.erc721);
+            call::invoke(
+                nft.call_mut().mint(
+                    token_id,
+                    domain_name.clone(),
+                    domain_owner,
+                    token_uri.clone(),
+                ),
+                self.read_call_limits(),
+            )??;
+
+            self.token_id = token_id;
+            self.domain_tokens.insert(domain_name.clone(), &token_id);
+
+            self.env().emit_event(NftMint {
+                domain_name,
+                domain_owner,
+                token_id,
+                token_uri,
+            });
+            Ok(())
+        }
+
+        /// Returns `amount` of a bid to `bidder`. A failed transfer is credited to be
+        /// withdrawn instead, so it cannot hold up the auction.
+        fn refund_bid(&mut self, bidder: AccountId, amount: Balance) -> Result<()> {
+            if amount > 0 && self.env().transfer(bidder, amount).is_err() {
+                self.credit(bidder, amount, None)?;
+            }
+            Ok(())
+        }
+
+        fn check_payment(&self, price: Balance) -> Result<()> {
+            if self.env().transferred_value() < price {
+                return Err(Error::InsufficientPayment);
+            }
+            Ok(())
+        }
+
+        /// Refunds whatever was sent on top of `price`.
+        fn refund_excess(&self, price: Balance) -> Result<()> {
+            let refund = self.env().transferred_value().saturating_sub(price);
+            if refund > 0 {
+                self.env()
+                    .transfer(self.env().caller(), refund)
+                    .map_err(|_| Error::TransferFailed)?;
+            }
+            Ok(())
+        }
+
+        /// Credits the referrer and partner shares of `fee`, paid out by `withdraw`
+        /// or `withdraw_token`, and keeps the rest in the treasury. Fees paid in a
+        /// token are reported per token rather than per TLD.
+        fn split_fee(
+            &mut self,
+            domain_name: &str,
+            fee: Balance,
+            referrer: Option<AccountId>,
+            payment_token: Option<AccountId>,
+        ) -> Result<()> {
+            let mut treasury_share = fee;
+
+            if let Some(referrer) = referrer {
+                let amount = bps_share(fee, self.fee_split.referrer_bps);
+                if amount > 0 {
+                    self.credit(referrer, amount, payment_token)?;
+                    treasury_share = treasury_share.saturating_sub(amount);
+                    self.env().emit_event(ReferralPaid {
+                        domain_name: String::from(domain_name),
+                        referrer,
+                        fee,
+                        amount,
+                    });
+                }
+            }
+            if let Some(partner) = self.fee_split.partner {
+                let amount = bps_share(fee, self.fee_split.partner_bps);
+                self.credit(partner, amount, payment_token)?;
+                treasury_share = treasury_share.saturating_sub(amount);
+            }
+
+            let revenue = Revenue {
+                fees: fee,
+                treasury: treasury_share,
+            };
+            let Some(token) = payment_token else {
+                self.treasury_balance = self
+                    .treasury_balance
+                    .checked_add(treasury_share)
+                    .ok_or(Error::UndefinedError)?;
+                self.record_revenue(name::tld_of(domain_name), revenue);
+                return Ok(());
+            };
+
+            let treasury = self
+                .read_token_treasury(token)
+                .checked_add(treasury_share)
+                .ok_or(Error::UndefinedError)?;
+            self.token_treasury.insert(token, &treasury);
+            let total = self.read_token_revenue(token);
+            self.token_revenue.insert(
+                token,
+                &Revenue {
+                    fees: total.fees.saturating_add(revenue.fees),
+                    treasury: total.treasury.saturating_add(revenue.treasury),
+                },
+            );
+            Ok(())
+        }
+
+        fn record_revenue(&mut self, tld: &str, revenue: Revenue) {
+            if revenue.fees == 0 {
+                return;
+            }
+            let tld = String::from(tld);
+            let add = |total: Revenue| Revenue {
+                fees: total.fees.saturating_add(revenue.fees),
+                treasury: total.treasury.saturating_add(revenue.treasury),
+            };
+
+            match self.tld_revenue.get(tld.clone()) {
+                Some(total) => self.tld_revenue.insert(tld.clone(), &add(total)),
+                None => {
+                    self.revenue_tlds.push(tld.clone());
+                    self.tld_revenue
+                        .insert(tld.clone(), &add(Revenue::default()))
+                }
+            };
+
+            let key = (tld, self.current_revenue_period());
+            let total = self.period_revenue.get(key.clone()).unwrap_or_default();
+            self.period_revenue.insert(key, &add(total));
+        }
+
+        fn credit(
+            &mut self,
+            account: AccountId,
+            amount: Balance,
+            token: Option<AccountId>,
+        ) -> Result<()> {
+            if amount == 0 {
+                return Ok(());
+            }
+            let balance = match token {
+                Some(token) => self.read_token_balance(token, account),
+                None => self.read_balance(account),
+            }
+            .checked_add(amount)
+            .ok_or(Error::UndefinedError)?;
+
+            match token {
+                Some(token) => self.token_balances.insert((token, account), &balance),
+                None => self.balances.insert(account, &balance),
+            };
+            Ok(())
+        }
+
+        /// Converts a native `price` to `token`, which has to be accepted for payments.
+        fn token_price(&self, token: AccountId, price: Balance) -> Result<Balance> {
+            if !self.is_payment_token(token) {
+                return Err(Error::UnsupportedToken);
+            }
+            let price_oracle = self.price_oracle();
+            call::invoke(
+                price_oracle.call().convert_to_token(token, price),
+                self.read_call_limits(),
+            )?
+            .ok_or(Error::PriceUnavailable)
+        }
+
+        /// Collects `amount` of `token` from the caller, who has to have approved it.
+        fn collect_token(&self, token: AccountId, amount: Balance) -> Result<()> {
+            if amount == 0 {
+                return Ok(());
+            }
+            let collected = call::invoke_selector::<MyEnvironment, _, PSP22Result>(
+                token,
+                self.read_call_limits(),
+                ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer_from")))
+                    .push_arg(self.env().caller())
+                    .push_arg(self.env().account_id())
+                    .push_arg(amount)
+                    .push_arg(Vec::<u8>::new()),
+            );
+            Ok(collected??)
+        }
+
+        fn send_token(&self, token: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+            let sent = call::invoke_selector::<MyEnvironment, _, PSP22Result>(
+                token,
+                self.read_call_limits(),
+                ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                    .push_arg(to)
+                    .push_arg(amount)
+                    .push_arg(Vec::<u8>::new()),
+            );
+            Ok(sent??)
+        }
+
+        fn consume_commitment(&mut self, _domain_name: String, commit_hash: Hash) -> Result<()> {
+            let current_time = self.env().block_timestamp();
+            let commitment = self
+                .commitments
+                .get(commit_hash)
+                .ok_or(Error::CommitmentNotFound)?;
+
+            if commitment.committer != self.env().caller() {
+                return Err(Error::NotCommitter);
+            }
+            if commitment.committed_at.add(self.min_commit_age) > current_time {
+                return Err(Error::CommitmentTooNew);
+            }
+            if commitment.committed_at.add(self.max_commit_age) <= current_time {
+                return Err(Error::CommitmentTooOld);
+            }
+            if self.reserved_names.contains(_domain_name.clone()) {
+                return Err(Error::NameReserved);
+            }
+            if self.auctions.contains(_domain_name.clone()) {
+                return Err(Error::NameInAuction);
+            }
+            if !self.check_domain_availablility(_domain_name)? {
+                return Err(Error::AlreadyRegistered);
+            }
+
+            // single use: a failed registration reverts this removal
+            self.commitments.remove(commit_hash);
+            Ok(())
+        }
+
+        fn is_commitment_expired(&self, commitment: &Commitment) -> bool {
+            commitment.committed_at.saturating_add(self.max_commit_age)
+                <= self.env().block_timestamp()
+        }
+
+        fn commitment_hash(
+            &self,
+            domain_name: String,
+            domain_owner: AccountId,
+            duration: Timestamp,
+            secret: [u8; 32],
+            resolver: AccountId,
+            records: &[RecordEntry],
+        ) -> Result<Hash> {
+            let (domain_name, tld) = self.normalize_name(&domain_name)?;
+            if duration < tld.min_registration_duration {
+                return Err(Error::DurationTooShort);
+            }
+
+            let mut commit = <Sha2x256 as HashOutput>::Type::default();
+            let commit_info =
+                self.create_commit_info(domain_name, domain_owner, duration, secret, resolver);
+
+            // without records the hash stays what it was before records existed
+            if records.is_empty() {
+                ink::env::hash_encoded::<Sha2x256, _>(&commit_info, &mut commit);
+            } else {
+                ink::env::hash_encoded::<Sha2x256, _>(&(commit_info, records), &mut commit);
+            }
+            Ok(Hash::from(commit))
+        }
+
+        fn create_commit_info(
+            &self,
+            domain_name: String,
+            domain_owner: AccountId,
+            duration: Timestamp,
+            secret: [u8; 32],
+            resolver: AccountId,
+        ) -> CommitInfo {
+            CommitInfo {
+                domain_name,
+                domain_owner,
+                duration,
+                secret,
+                resolver,
+                committer: self.env().caller(),
+            }
+        }
+
+        fn create_domain_info(
+            &self,
+            domain_name: String,
+            domain_owner: AccountId,
+            domain_expiry_time: Timestamp,
+        ) -> DomainInfo {
+            DomainInfo {
+                domain_name,
+                domain_owner,
+                domain_expiry_time,
+            }
+        }
+
+        /// Normalizes `domain_name`, which has to be a single label in front of a
+        /// supported TLD, and looks up the config of its TLD.
+        fn normalize_name(&self, domain_name: &str) -> Result<(String, TldConfig)> {
+            let domain_name =
+                name::normalize(domain_name).map_err(|reason| Error::InvalidName { reason })?;
+            let tld = self
+                .tlds
+                .get(String::from(name::tld_of(&domain_name)))
+                .ok_or(Error::UnsupportedTld)?;
+            if name::is_subdomain(&domain_name) {
+                return Err(Error::InvalidName {
+                    reason: InvalidNameReason::Subdomain,
+                });
+            }
+            Ok((domain_name, tld))
+        }
+
+        /// Writes the record of a registration expiring at `domain_expiry_time`.
+        fn set_record(
+            &self,
+            label_hash: Hash,
+            request: &RegistrationRequest,
+            domain_expiry_time: Timestamp,
+        ) -> Result<()> {
+            // `request.resolver` is stored in the record, the call goes to the registrar's resolver
+            let mut resolver_contract = self.resolver();
+            Ok(call::invoke(
+                resolver_contract.call_mut().set_record(
+                    label_hash,
+                    request.domain_name.clone(),
+                    request.domain_owner,
+                    request.duration,
+                    request.secret,
+                    request.resolver,
+                    domain_expiry_time,
+                ),
+                self.read_call_limits(),
+            )??)
+        }
+
+        /// Writes `records` of a domain just registered, as the resolver's controller.
+        fn set_resolver_records(
+            &self,
+            domain_name: String,
+            records: Vec<RecordEntry>,
+        ) -> Result<()> {
+            let mut resolver = self.resolver();
+            Ok(call::invoke(
+                resolver.call_mut().set_records(domain_name, records),
+                self.read_call_limits(),
+            )??)
+        }
+
+        fn quote_domain_price(
+            &self,
+            domain_name: String,
+            duration: Timestamp,
+        ) -> Result<PriceQuote> {
+            let price_oracle = self.price_oracle();
+            Ok(call::invoke(
+                price_oracle.call().quote(domain_name, duration),
+                self.read_call_limits(),
+            )??)
+        }
+
+        fn is_premium_name(&self, domain_name: String) -> Result<bool> {
+            let price_oracle = self.price_oracle();
+            Ok(call::invoke(
+                price_oracle.call().is_premium_name(domain_name),
+                self.read_call_limits(),
+            )?)
+        }
+
+        fn renew_domain(&self, domain_name: String, duration: Timestamp) -> Result<()> {
+            let mut resolver = self.resolver();
+            Ok(call::invoke(
+                resolver.call_mut().renew_domain(domain_name, duration),
+                self.read_call_limits(),
+            )??)
+        }
+
+        fn resolver(&self) -> contract_ref!(VnsResolver, MyEnvironment) {
+            FromAccountId::from_account_id(self.resolver_contract_address)
+        }
+
+        fn price_oracle(&self) -> contract_ref!(VnsPriceOracle, MyEnvironment) {
+            FromAccountId::from_account_id(self.price_oracle_contract_address)
+        }
+    }
+
+    /// Fails if `price` is above the caller's `max_price`.
+    fn check_max_price(max_price: Option<Balance>, price: Balance) -> Result<()> {
+        if max_price.is_some_and(|max_price| price > max_price) {
+            return Err(Error::PriceAboveMax);
+        }
+        Ok(())
+    }
+
+    /// `bps` basis points of `amount`, rounded down.
+    fn bps_share(amount: Balance, bps: u16) -> Balance {
+        let bps = Balance::from(bps);
+        let denominator = Balance::from(BPS_DENOMINATOR);
+        // split up so large amounts cannot overflow
+        (amount / denominator) * bps + (amount % denominator) * bps / denominator
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::cell::RefCell;
+        use std::collections::BTreeMap;
+        use std::rc::Rc;
+        use vns_common::price::PriceOracleError;
+        use vns_common::testing::{
+            answer, decode_args, mock_callee, set_caller, trap, unknown_message, unmock_callee,
+        };
+
+        const RESOLVER: AccountId = [0x2; 20];
+        const PRICE_ORACLE: AccountId = [0x3; 20];
+        const NFT: AccountId = [0x4; 20];
+
+        fn new_registrar() -> Registrar {
+            Registrar::new(
+                AccountId::from([0x1; 20]),
+                100,
+                10,
+                1000,
+                RESOLVER,
+                PRICE_ORACLE,
+                NFT,
+            )
+        }
+
+        /// What the mocked resolver, price oracle and NFT know.
+        #[derive(Default)]
+        struct Callees {
+            /// Registered domains with their owner and expiry time.
+            domains: BTreeMap<String, (AccountId, Timestamp)>,
+            premium_names: Vec<String>,
+            /// Minted tokens with their domain and owner.
+            minted: Vec<(u64, String, AccountId)>,
+        }
+
+        /// Mocks the resolver, price oracle and NFT of `new_registrar`. A name costs
+        /// its duration, ten times that if it is a premium name, and tokens are
+        /// worth half the native balance.
+        fn mock_callees() -> Rc<RefCell<Callees>> {
+            let callees = Rc::new(RefCell::new(Callees::default()));
+
+            let state = callees.clone();
+            mock_callee(RESOLVER, move |selector, args| {
+                let mut state = state.borrow_mut();
+                match selector {
+                    ink::selector_bytes!("set_record") => {
+                        let (_, domain_name, domain_owner, _, _, _, expiry_time): (
+                            Hash,
+                            String,
+                            AccountId,
+                            Timestamp,
+                            [u8; 32],
+                            AccountId,
+                            Timestamp,
+                        ) = decode_args(args);
+                        if state.domains.contains_key(&domain_name) {
+                            return answer(Err::<(), _>(ResolverError::DomainAlreadyRegistered));
+                        }
+                        state
+                            .domains
+                            .insert(domain_name, (domain_owner, expiry_time));
+                        answer(Ok::<(), ResolverError>(()))
+                    }
+                    ink::selector_bytes!("set_records") => answer(Ok::<(), ResolverError>(())),
+                    ink::selector_bytes!("renew_domain") => {
+                        let (domain_name, duration): (String, Timestamp) = decode_args(args);
+                        let Some(domain) = state.domains.get_mut(&domain_name) else {
+                            return answer(Err::<(), _>(ResolverError::DomainNotRegistered));
+                        };
+                        domain.1 += duration;
+                        answer(Ok::<(), ResolverError>(()))
+                    }
+                    ink::selector_bytes!("check_domain_availablility") => {
+                        let domain_name: String = decode_args(args);
+                        answer(!state.domains.contains_key(&domain_name))
+                    }
+                    ink::selector_bytes!("read_grace_period") => answer(0 as Timestamp),
+                    ink::selector_bytes!("read_domain_owner") => {
+                        let domain_name: String = decode_args(args);
+                        match state.domains.get(&domain_name) {
+                            Some(domain) => answer(Ok::<_, ResolverError>(domain.0)),
+                            None => answer(Err::<AccountId, _>(ResolverError::DomainNotRegistered)),
+                        }
+                    }
+                    _ => unknown_message(),
+                }
+            });
+
+            let state = callees.clone();
+            mock_callee(PRICE_ORACLE, move |selector, args| {
+                let state = state.borrow();
+                match selector {
+                    ink::selector_bytes!("quote") => {
+                        let (name, duration): (String, Timestamp) = decode_args(args);
+                        let multiplier = if state.premium_names.contains(&name) {
+                            10
+                        } else {
+                            1
+                        };
+                        answer(PriceQuote::new(0, duration.into(), multiplier, 0))
+                    }
+                    ink::selector_bytes!("is_premium_name") => {
+                        let name: String = decode_args(args);
+                        answer(state.premium_names.contains(&name))
+                    }
+                    ink::selector_bytes!("convert_to_token") => {
+                        let (_, amount): (AccountId, Balance) = decode_args(args);
+                        answer(Some(amount * 2))
+                    }
+                    ink::selector_bytes!("record_release") => {
+                        answer(Ok::<(), PriceOracleError>(()))
+                    }
+                    _ => unknown_message(),
+                }
+            });
+
+            let state = callees.clone();
+            mock_callee(NFT, move |selector, args| match selector {
+                ink::selector_bytes!("mint") => {
+                    let (id, domain_name, owner, _): (u64, String, AccountId, String) =
+                        decode_args(args);
+                    state.borrow_mut().minted.push((id, domain_name, owner));
+                    answer(Ok::<(), NftError>(()))
+                }
+                _ => unknown_message(),
+            });
+
+            callees
+        }
+
+        fn advance_time(duration: Timestamp) {
+            let now = ink::env::block_timestamp::<MyEnvironment>();
+            ink::env::test::set_block_timestamp::<MyEnvironment>(now + duration);
+        }
+
+        /// A request to register `domain_name` to `owner`, without any extras.
+        fn registration_request(
+            domain_name: &str,
+            owner: AccountId,
+            duration: Timestamp,
+        ) -> RegistrationRequest {
+            RegistrationRequest {
+                domain_name: domain_name.to_string(),
+                domain_owner: owner,
+                duration,
+                secret: [0x7; 32],
+                resolver: RESOLVER,
+                token_uri: None,
+                referrer: None,
+                allowlist_proof: None,
+                voucher: None,
+                max_price: None,
+                records: Vec::new(),
+            }
+        }
+
+        /// Commits to every request, without waiting for the commitments to mature.
+        fn commit_requests(registrar: &mut Registrar, requests: &[RegistrationRequest]) {
+            let commit_hashes = requests
+                .iter()
+                .map(|request| {
+                    registrar
+                        .make_commitment(
+                            request.domain_name.clone(),
+                            request.domain_owner,
+                            request.duration,
+                            request.secret,
+                            request.resolver,
+                        )
+                        .unwrap()
+                })
+                .collect();
+            assert_eq!(registrar.commit_many(commit_hashes), Ok(()));
+        }
+
+        /// Commits to `request` and registers it once the commitment is old enough,
+        /// paying `payment`.
+        fn register_committed(
+            registrar: &mut Registrar,
+            request: RegistrationRequest,
+            payment: Balance,
+        ) -> Result<()> {
+            commit_requests(registrar, std::slice::from_ref(&request));
+            advance_time(10);
+
+            ink::env::test::set_value_transferred::<MyEnvironment>(payment);
+            let registered = registrar.register(request);
+            ink::env::test::set_value_transferred::<MyEnvironment>(0);
+            registered
+        }
+
+        /// Commits to `domain_name` and registers it to `owner` once the commitment is
+        /// old enough, paying `payment`.
+        fn commit_and_register(
+            registrar: &mut Registrar,
+            domain_name: &str,
+            owner: AccountId,
+            duration: Timestamp,
+            payment: Balance,
+        ) -> Result<()> {
+            let request = registration_request(domain_name, owner, duration);
+            let commit_hash = registrar.make_commitment(
+                request.domain_name.clone(),
+                owner,
+                duration,
+                request.secret,
+                RESOLVER,
+            )?;
+            registrar.commit(commit_hash)?;
+            advance_time(10);
+
+            ink::env::test::set_value_transferred::<MyEnvironment>(payment);
+            let registered = registrar.register(request);
+            ink::env::test::set_value_transferred::<MyEnvironment>(0);
+            registered
+        }
+
+        #[ink::test]
+        fn commitment_status_works() {
+            let mut registrar = new_registrar();
+            let commit_hash = [0x5; 32];
+            assert_eq!(
+                registrar.commitment_status(commit_hash),
+                CommitmentStatus::Unknown
+            );
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            assert_eq!(registrar.commit(commit_hash), Ok(()));
+            assert_eq!(
+                registrar.commitment_status(commit_hash),
+                CommitmentStatus::Pending { ready_at: 1010 }
+            );
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1010);
+            assert_eq!(
+                registrar.commitment_status(commit_hash),
+                CommitmentStatus::Ready { expires_at: 1100 }
+            );
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1100);
+            assert_eq!(
+                registrar.commitment_status(commit_hash),
+                CommitmentStatus::Expired
+            );
+        }
+
+        #[ink::test]
+        fn commit_replaces_only_expired_commitments() {
+            let mut registrar = new_registrar();
+            let commit_hash = [0x5; 32];
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            assert_eq!(registrar.commit(commit_hash), Ok(()));
+            assert_eq!(
+                registrar.commit(commit_hash),
+                Err(Error::UnexpiredCommitmentExists)
+            );
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1200);
+            assert_eq!(registrar.commit(commit_hash), Ok(()));
+            assert_eq!(
+                registrar.commitment_status(commit_hash),
+                CommitmentStatus::Pending { ready_at: 1210 }
+            );
+        }
+
+        #[ink::test]
+        fn prune_commitments_removes_expired_only() {
+            let mut registrar = new_registrar();
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            assert_eq!(registrar.commit([0x5; 32]), Ok(()));
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1050);
+            assert_eq!(registrar.commit([0x6; 32]), Ok(()));
+
+            // only the first commitment is past max_commit_age
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1120);
+            assert_eq!(
+                registrar.prune_commitments(vec![[0x5; 32], [0x6; 32], [0x7; 32]]),
+                Ok(1)
+            );
+            assert_eq!(
+                registrar.commitment_status([0x5; 32]),
+                CommitmentStatus::Unknown
+            );
+            assert_eq!(
+                registrar.commitment_status([0x6; 32]),
+                CommitmentStatus::Ready { expires_at: 1150 }
+            );
+        }
+
+        #[ink::test]
+        fn make_commitment_normalizes_names() {
+            let registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            let resolver = AccountId::from([0x2; 20]);
+
+            assert_eq!(
+                registrar.make_commitment(
+                    "Alice.VNE".to_string(),
+                    owner,
+                    2000,
+                    [0x1; 32],
+                    resolver
+                ),
+                registrar.make_commitment(
+                    "alice.vne".to_string(),
+                    owner,
+                    2000,
+                    [0x1; 32],
+                    resolver
+                )
+            );
+            assert_eq!(
+                registrar.make_commitment(
+                    "a.vne.evil".to_string(),
+                    owner,
+                    2000,
+                    [0x1; 32],
+                    resolver
+                ),
+                Err(Error::UnsupportedTld)
+            );
+            assert_eq!(
+                registrar.make_commitment(".vne".to_string(), owner, 2000, [0x1; 32], resolver),
+                Err(Error::InvalidName {
+                    reason: InvalidNameReason::EmptyLabel
+                })
+            );
+            assert_eq!(
+                registrar.make_commitment("alice.vne".to_string(), owner, 10, [0x1; 32], resolver),
+                Err(Error::DurationTooShort)
+            );
+            assert_eq!(
+                registrar.make_commitment(
+                    "pay.alice.vne".to_string(),
+                    owner,
+                    2000,
+                    [0x1; 32],
+                    resolver
+                ),
+                Err(Error::InvalidName {
+                    reason: InvalidNameReason::Subdomain
+                })
+            );
+        }
+
+        #[ink::test]
+        fn commitments_are_bound_to_the_caller() {
+            mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            let commit_hash = registrar
+                .make_commitment("alice.vne".to_string(), owner, 2000, [0x7; 32], RESOLVER)
+                .unwrap();
+
+            // bob copies the hash, but registering recomputes it with bob as the committer
+            set_caller(AccountId::from([0x2; 20]));
+            assert_ne!(
+                registrar.make_commitment(
+                    "alice.vne".to_string(),
+                    owner,
+                    2000,
+                    [0x7; 32],
+                    RESOLVER
+                ),
+                Ok(commit_hash)
+            );
+            assert_eq!(registrar.commit(commit_hash), Ok(()));
+            advance_time(10);
+            ink::env::test::set_value_transferred::<MyEnvironment>(2000);
+            assert_eq!(
+                registrar.register(registration_request("alice.vne", owner, 2000)),
+                Err(Error::CommitmentNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn pause_blocks_state_changes() {
+            let mut registrar = new_registrar();
+            assert!(!registrar.is_paused());
+
+            assert_eq!(registrar.pause(), Ok(()));
+            assert!(registrar.is_paused());
+            assert_eq!(registrar.commit([0x5; 32]), Err(Error::Paused));
+            assert_eq!(
+                registrar.commit_many(vec![[0x6; 32]]),
+                Err(BatchError::Batch(Error::Paused))
+            );
+            assert_eq!(
+                registrar.prune_commitments(vec![[0x5; 32]]),
+                Err(Error::Paused)
+            );
+            assert_eq!(registrar.withdraw(), Err(Error::Paused));
+            // reads keep working
+            assert_eq!(
+                registrar.commitment_status([0x5; 32]),
+                CommitmentStatus::Unknown
+            );
+
+            assert_eq!(registrar.unpause(), Ok(()));
+            assert!(!registrar.is_paused());
+            assert_eq!(registrar.commit([0x5; 32]), Ok(()));
+        }
+
+        #[ink::test]
+        fn only_pausers_can_pause() {
+            let mut registrar = new_registrar();
+            let pauser = AccountId::from([0x9; 20]);
+            set_caller(pauser);
+            assert_eq!(
+                registrar.pause(),
+                Err(Error::Access(AccessError::MissingRole { role: PAUSER }))
+            );
+
+            set_caller(AccountId::from([0x1; 20]));
+            assert_eq!(registrar.grant_role(PAUSER, pauser), Ok(()));
+            set_caller(pauser);
+            assert_eq!(registrar.pause(), Ok(()));
+        }
+
+        #[ink::test]
+        fn admin_transfer_takes_two_steps() {
+            let mut registrar = new_registrar();
+            let admin = AccountId::from([0x1; 20]);
+            let new_admin = AccountId::from([0x9; 20]);
+
+            assert_eq!(registrar.propose_admin(new_admin), Ok(()));
+            assert_eq!(registrar.read_admin(), admin);
+            assert_eq!(registrar.read_pending_admin(), Some(new_admin));
+
+            set_caller(new_admin);
+            assert_eq!(registrar.accept_admin(), Ok(()));
+            assert_eq!(registrar.read_admin(), new_admin);
+            assert_eq!(registrar.set_max_commit_age(200), Ok(()));
+
+            set_caller(admin);
+            assert_eq!(
+                registrar.set_max_commit_age(300),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+
+        #[ink::test]
+        fn add_and_remove_tld_works() {
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            let resolver = AccountId::from([0x2; 20]);
+            assert_eq!(registrar.read_supported_tlds(), vec!["vne".to_string()]);
+
+            assert_eq!(registrar.add_tld("DAO".to_string(), 5000), Ok(()));
+            assert_eq!(
+                registrar.add_tld("dao".to_string(), 5000),
+                Err(Error::TldAlreadySupported)
+            );
+            assert_eq!(
+                registrar.read_min_registration_duration("dao".to_string()),
+                Some(5000)
+            );
+            // each TLD has its own minimum duration
+            assert_eq!(
+                registrar.make_commitment(
+                    "alice.dao".to_string(),
+                    owner,
+                    2000,
+                    [0x1; 32],
+                    resolver
+                ),
+                Err(Error::DurationTooShort)
+            );
+            assert!(registrar
+                .make_commitment("alice.dao".to_string(), owner, 5000, [0x1; 32], resolver)
+                .is_ok());
+
+            assert_eq!(registrar.remove_tld("dao".to_string()), Ok(()));
+            assert_eq!(registrar.read_supported_tlds(), vec!["vne".to_string()]);
+            assert_eq!(
+                registrar.make_commitment(
+                    "alice.dao".to_string(),
+                    owner,
+                    5000,
+                    [0x1; 32],
+                    resolver
+                ),
+                Err(Error::UnsupportedTld)
+            );
+        }
+
+        #[ink::test]
+        fn reserve_name_works() {
+            let mut registrar = new_registrar();
+            assert_eq!(registrar.reserve_name("Admin.vne".to_string()), Ok(()));
+            assert!(registrar.is_reserved("admin.vne".to_string()));
+            assert_eq!(
+                registrar.reserve_name("admin.dao".to_string()),
+                Err(Error::UnsupportedTld)
+            );
+
+            assert_eq!(registrar.unreserve_name("admin.vne".to_string()), Ok(()));
+            assert!(!registrar.is_reserved("admin.vne".to_string()));
+            assert_eq!(
+                registrar.unreserve_name("admin.vne".to_string()),
+                Err(Error::NameNotReserved)
+            );
+        }
+
+        #[ink::test]
+        fn set_fee_split_works() {
+            let mut registrar = new_registrar();
+            assert_eq!(registrar.read_fee_split(), FeeSplit::default());
+
+            let fee_split = FeeSplit {
+                referrer_bps: 1000,
+                partner_bps: 500,
+                partner: Some(AccountId::from([0x7; 20])),
+            };
+            assert_eq!(registrar.set_fee_split(fee_split.clone()), Ok(()));
+            assert_eq!(registrar.read_fee_split(), fee_split);
+
+            assert_eq!(
+                registrar.set_fee_split(FeeSplit {
+                    referrer_bps: 9000,
+                    partner_bps: 1001,
+                    partner: None,
+                }),
+                Err(Error::InvalidFeeSplit)
+            );
+            assert_eq!(registrar.read_fee_split(), fee_split);
+        }
+
+        #[ink::test]
+        fn split_fee_credits_every_share() {
+            let mut registrar = new_registrar();
+            let referrer = AccountId::from([0x6; 20]);
+            let partner = AccountId::from([0x7; 20]);
+            registrar
+                .set_fee_split(FeeSplit {
+                    referrer_bps: 1000,
+                    partner_bps: 500,
+                    partner: Some(partner),
+                })
+                .unwrap();
+
+            assert_eq!(
+                registrar.split_fee("alice.vne", 10_000, Some(referrer), None),
+                Ok(())
+            );
+            assert_eq!(registrar.read_balance(referrer), 1000);
+            assert_eq!(registrar.read_balance(partner), 500);
+            assert_eq!(registrar.read_treasury_balance(), 8500);
+
+            // without a referrer its share stays with the treasury
+            assert_eq!(registrar.split_fee("bob.vne", 10_000, None, None), Ok(()));
+            assert_eq!(registrar.read_balance(referrer), 1000);
+            assert_eq!(registrar.read_balance(partner), 1000);
+            assert_eq!(registrar.read_treasury_balance(), 18_000);
+        }
+
+        #[ink::test]
+        fn revenue_report_works() {
+            let mut registrar = new_registrar();
+            registrar.add_tld(String::from("web"), 1000).unwrap();
+            assert_eq!(registrar.revenue_report(), vec![]);
+
+            registrar
+                .set_fee_split(FeeSplit {
+                    referrer_bps: 1000,
+                    partner_bps: 0,
+                    partner: None,
+                })
+                .unwrap();
+            registrar
+                .split_fee("alice.vne", 1000, Some(AccountId::from([0x6; 20])), None)
+                .unwrap();
+            registrar.split_fee("bob.web", 500, None, None).unwrap();
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(REVENUE_PERIOD);
+            assert_eq!(registrar.current_revenue_period(), 1);
+            registrar.split_fee("carol.vne", 2000, None, None).unwrap();
+
+            assert_eq!(
+                registrar.revenue_report(),
+                vec![
+                    TldRevenue {
+                        tld: String::from("vne"),
+                        revenue: Revenue {
+                            fees: 3000,
+                            treasury: 2900
+                        },
+                    },
+                    TldRevenue {
+                        tld: String::from("web"),
+                        revenue: Revenue {
+                            fees: 500,
+                            treasury: 500
+                        },
+                    },
+                ]
+            );
+            assert_eq!(
+                registrar.revenue_report_for_period(1),
+                vec![
+                    TldRevenue {
+                        tld: String::from("vne"),
+                        revenue: Revenue {
+                            fees: 2000,
+                            treasury: 2000
+                        },
+                    },
+                    TldRevenue {
+                        tld: String::from("web"),
+                        revenue: Revenue::default(),
+                    },
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn withdraw_treasury_checks_balance() {
+            let mut registrar = new_registrar();
+            registrar.split_fee("alice.vne", 1000, None, None).unwrap();
+            assert_eq!(
+                registrar.withdraw_treasury(1001, AccountId::from([0x8; 20])),
+                Err(Error::InsufficientTreasury)
+            );
+            assert_eq!(registrar.read_treasury_balance(), 1000);
+        }
+
+        #[ink::test]
+        fn withdraw_without_balance_fails() {
+            let mut registrar = new_registrar();
+            assert_eq!(registrar.withdraw(), Err(Error::NothingToWithdraw));
+        }
+
+        #[ink::test]
+        fn bps_share_rounds_down() {
+            assert_eq!(bps_share(10_000, 250), 250);
+            assert_eq!(bps_share(999, 1), 0);
+            assert_eq!(bps_share(Balance::MAX, BPS_DENOMINATOR), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn upgrade_requires_admin() {
+            let mut registrar = new_registrar();
+            set_caller(AccountId::from([0x9; 20]));
+            assert_eq!(
+                registrar.upgrade([0x5; 32]),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+
+        fn launch_schedule(merkle_root: Hash) -> LaunchSchedule {
+            LaunchSchedule {
+                phases: vec![
+                    AllowlistPhase {
+                        starts_at: 1000,
+                        merkle_root,
+                        discount_bps: 2_000,
+                    },
+                    AllowlistPhase {
+                        starts_at: 2000,
+                        merkle_root,
+                        discount_bps: 0,
+                    },
+                ],
+                public_opens_at: 3000,
+            }
+        }
+
+        #[ink::test]
+        fn registration_phase_follows_schedule() {
+            let mut registrar = new_registrar();
+            assert_eq!(registrar.registration_phase(), RegistrationPhase::Public);
+            registrar
+                .set_launch_schedule(Some(launch_schedule([0x1; 32])))
+                .unwrap();
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(999);
+            assert_eq!(registrar.registration_phase(), RegistrationPhase::Closed);
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            assert_eq!(
+                registrar.registration_phase(),
+                RegistrationPhase::Allowlist { index: 0 }
+            );
+            ink::env::test::set_block_timestamp::<MyEnvironment>(2500);
+            assert_eq!(
+                registrar.registration_phase(),
+                RegistrationPhase::Allowlist { index: 1 }
+            );
+            ink::env::test::set_block_timestamp::<MyEnvironment>(3000);
+            assert_eq!(registrar.registration_phase(), RegistrationPhase::Public);
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            registrar.set_launch_schedule(None).unwrap();
+            assert_eq!(registrar.registration_phase(), RegistrationPhase::Public);
+            assert_eq!(registrar.read_launch_schedule(), None);
+        }
+
+        #[ink::test]
+        fn set_launch_schedule_validates_phases() {
+            let mut registrar = new_registrar();
+
+            let mut unordered = launch_schedule([0x1; 32]);
+            unordered.phases[1].starts_at = 1000;
+            assert_eq!(
+                registrar.set_launch_schedule(Some(unordered)),
+                Err(Error::InvalidSchedule)
+            );
+            let mut late = launch_schedule([0x1; 32]);
+            late.public_opens_at = 2000;
+            assert_eq!(
+                registrar.set_launch_schedule(Some(late)),
+                Err(Error::InvalidSchedule)
+            );
+            let mut discount = launch_schedule([0x1; 32]);
+            discount.phases[0].discount_bps = BPS_DENOMINATOR + 1;
+            assert_eq!(
+                registrar.set_launch_schedule(Some(discount)),
+                Err(Error::InvalidSchedule)
+            );
+
+            set_caller(AccountId::from([0x9; 20]));
+            assert_eq!(
+                registrar.set_launch_schedule(Some(launch_schedule([0x1; 32]))),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+
+        #[ink::test]
+        fn redeem_allowlist_verifies_proofs() {
+            let mut registrar = new_registrar();
+            let alice = AccountId::from([0x1; 20]);
+            let bob = AccountId::from([0x2; 20]);
+            let alice_leaf = registrar
+                .make_allowlist_leaf(alice, Some(String::from("Alice.vne")))
+                .unwrap();
+            let bob_leaf = registrar.make_allowlist_leaf(bob, None).unwrap();
+            let root = merkle::hash_pair(&alice_leaf, &bob_leaf);
+            registrar
+                .set_launch_schedule(Some(launch_schedule(root)))
+                .unwrap();
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(500);
+            assert_eq!(
+                registrar.redeem_allowlist("alice.vne", &None),
+                Err(Error::RegistrationNotOpen)
+            );
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+            let alice_proof = Some(AllowlistProof {
+                name: Some(String::from("alice.vne")),
+                proof: vec![bob_leaf],
+            });
+            assert_eq!(
+                registrar.redeem_allowlist("alice.vne", &None),
+                Err(Error::ProofRequired)
+            );
+            assert_eq!(
+                registrar.redeem_allowlist("alice.vne", &alice_proof),
+                Ok(2_000)
+            );
+            assert_eq!(
+                registrar.redeem_allowlist("alice.vne", &alice_proof),
+                Err(Error::AllowlistEntryRedeemed)
+            );
+            // the entry is tied to one name
+            assert_eq!(
+                registrar.redeem_allowlist("other.vne", &alice_proof),
+                Err(Error::InvalidProof)
+            );
+
+            let bob_proof = Some(AllowlistProof {
+                name: None,
+                proof: vec![alice_leaf],
+            });
+            assert_eq!(
+                registrar.redeem_allowlist("bob.vne", &bob_proof),
+                Err(Error::InvalidProof)
+            );
+            set_caller(bob);
+            assert_eq!(
+                registrar.redeem_allowlist("anything.vne", &bob_proof),
+                Ok(2_000)
+            );
+            // an entry without a name still registers only one
+            assert_eq!(
+                registrar.redeem_allowlist("else.vne", &bob_proof),
+                Err(Error::AllowlistEntryRedeemed)
+            );
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(3000);
+            assert_eq!(registrar.redeem_allowlist("bob.vne", &None), Ok(0));
+        }
+
+        #[ink::test]
+        fn allowlist_entries_register_one_name() {
+            let callees = mock_callees();
+            let mut registrar = new_registrar();
+            let alice = AccountId::from([0x1; 20]);
+            // a tree of alice's entry alone, for any name
+            let root = registrar.make_allowlist_leaf(alice, None).unwrap();
+            registrar
+                .set_launch_schedule(Some(launch_schedule(root)))
+                .unwrap();
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1000);
+
+            let without_proof = registration_request("bob.vne", alice, 2000);
+            assert_eq!(
+                register_committed(&mut registrar, without_proof, 2000),
+                Err(Error::ProofRequired)
+            );
+            let mut request = registration_request("alice.vne", alice, 2000);
+            request.allowlist_proof = Some(AllowlistProof {
+                name: None,
+                proof: Vec::new(),
+            });
+            // 20% off in the first phase
+            assert_eq!(
+                register_committed(&mut registrar, request.clone(), 1600),
+                Ok(())
+            );
+            assert_eq!(
+                callees
+                    .borrow()
+                    .domains
+                    .get("alice.vne")
+                    .map(|domain| domain.0),
+                Some(alice)
+            );
+
+            request.domain_name = String::from("alice2.vne");
+            assert_eq!(
+                register_committed(&mut registrar, request, 2000),
+                Err(Error::AllowlistEntryRedeemed)
+            );
+            assert!(!callees.borrow().domains.contains_key("alice2.vne"));
+        }
+
+        #[ink::test]
+        fn add_voucher_validates_discount() {
+            let mut registrar = new_registrar();
+            let voucher_hash = registrar.make_voucher_hash(String::from("LAUNCH"));
+            for (discount, max_uses) in [
+                (Discount::Percent { bps: 0 }, 1),
+                (
+                    Discount::Percent {
+                        bps: BPS_DENOMINATOR + 1,
+                    },
+                    1,
+                ),
+                (Discount::Fixed { amount: 0 }, 1),
+                (Discount::Fixed { amount: 10 }, 0),
+            ] {
+                assert_eq!(
+                    registrar.add_voucher(voucher_hash, discount, max_uses, 100),
+                    Err(Error::InvalidDiscount)
+                );
+            }
+
+            set_caller(AccountId::from([0x9; 20]));
+            assert_eq!(
+                registrar.add_voucher(voucher_hash, Discount::Fixed { amount: 10 }, 1, 100),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+
+        #[ink::test]
+        fn redeem_voucher_discounts_price() {
+            let mut registrar = new_registrar();
+            let percent = registrar.make_voucher_hash(String::from("QUARTER"));
+            let fixed = registrar.make_voucher_hash(String::from("MINUS500"));
+            registrar
+                .add_voucher(percent, Discount::Percent { bps: 2_500 }, 2, 100)
+                .unwrap();
+            registrar
+                .add_voucher(fixed, Discount::Fixed { amount: 500 }, 1, 100)
+                .unwrap();
+
+            assert_eq!(
+                registrar.redeem_voucher(String::from("QUARTER"), "alice.vne", 1000),
+                Ok(750)
+            );
+            // a fixed discount never takes the price below zero
+            assert_eq!(
+                registrar.redeem_voucher(String::from("MINUS500"), "alice.vne", 300),
+                Ok(0)
+            );
+            assert_eq!(
+                registrar.redeem_voucher(String::from("WRONG"), "alice.vne", 1000),
+                Err(Error::VoucherNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn voucher_cannot_be_reused_past_limits() {
+            let mut registrar = new_registrar();
+            let voucher_hash = registrar.make_voucher_hash(String::from("ONCE"));
+            registrar
+                .add_voucher(voucher_hash, Discount::Fixed { amount: 100 }, 1, 100)
+                .unwrap();
+
+            assert_eq!(
+                registrar.redeem_voucher(String::from("ONCE"), "a.vne", 1000),
+                Ok(900)
+            );
+            assert_eq!(registrar.read_voucher(voucher_hash).unwrap().redeemed, 1);
+            assert_eq!(
+                registrar.redeem_voucher(String::from("ONCE"), "b.vne", 1000),
+                Err(Error::VoucherExhausted)
+            );
+
+            registrar
+                .add_voucher(voucher_hash, Discount::Fixed { amount: 100 }, 1, 100)
+                .unwrap();
+            ink::env::test::set_block_timestamp::<MyEnvironment>(100);
+            assert_eq!(
+                registrar.redeem_voucher(String::from("ONCE"), "b.vne", 1000),
+                Err(Error::VoucherExpired)
+            );
+
+            assert_eq!(registrar.remove_voucher(voucher_hash), Ok(true));
+            assert_eq!(registrar.remove_voucher(voucher_hash), Ok(false));
+        }
+
+        #[ink::test]
+        fn payment_tokens_are_whitelisted() {
+            let mut registrar = new_registrar();
+            let token = AccountId::from([0x5; 20]);
+            assert!(!registrar.is_payment_token(token));
+            assert_eq!(
+                registrar.token_price(token, 100),
+                Err(Error::UnsupportedToken)
+            );
+
+            assert_eq!(registrar.add_payment_token(token), Ok(()));
+            assert!(registrar.is_payment_token(token));
+            assert_eq!(registrar.remove_payment_token(token), Ok(true));
+            assert_eq!(registrar.remove_payment_token(token), Ok(false));
+
+            set_caller(AccountId::from([0x9; 20]));
+            assert_eq!(
+                registrar.add_payment_token(token),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+
+        #[ink::test]
+        fn split_fee_keeps_tokens_apart() {
+            let mut registrar = new_registrar();
+            let token = AccountId::from([0x5; 20]);
+            let referrer = AccountId::from([0x6; 20]);
+            registrar
+                .set_fee_split(FeeSplit {
+                    referrer_bps: 1000,
+                    partner_bps: 0,
+                    partner: None,
+                })
+                .unwrap();
+
+            registrar
+                .split_fee("alice.vne", 10_000, Some(referrer), Some(token))
+                .unwrap();
+            assert_eq!(registrar.read_token_balance(token, referrer), 1000);
+            assert_eq!(registrar.read_token_treasury(token), 9000);
+            assert_eq!(
+                registrar.read_token_revenue(token),
+                Revenue {
+                    fees: 10_000,
+                    treasury: 9000
+                }
+            );
+            // nothing is counted in the native balance
+            assert_eq!(registrar.read_balance(referrer), 0);
+            assert_eq!(registrar.read_treasury_balance(), 0);
+            assert_eq!(registrar.revenue_report(), vec![]);
+
+            assert_eq!(
+                registrar.withdraw_treasury_token(token, 9001, referrer),
+                Err(Error::InsufficientTreasury)
+            );
+            set_caller(AccountId::from([0x9; 20]));
+            assert_eq!(
+                registrar.withdraw_token(token),
+                Err(Error::NothingToWithdraw)
+            );
+        }
+
+        #[ink::test]
+        fn check_max_price_works() {
+            assert_eq!(check_max_price(None, Balance::MAX), Ok(()));
+            assert_eq!(check_max_price(Some(1000), 1000), Ok(()));
+            assert_eq!(check_max_price(Some(1000), 1001), Err(Error::PriceAboveMax));
+        }
+
+        #[ink::test]
+        fn register_checks_max_price() {
+            let callees = mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+
+            let mut request = registration_request("alice.vne", owner, 2000);
+            request.max_price = Some(1999);
+            assert_eq!(
+                register_committed(&mut registrar, request, 2000),
+                Err(Error::PriceAboveMax)
+            );
+            assert!(!callees.borrow().domains.contains_key("alice.vne"));
+            assert_eq!(registrar.read_treasury_balance(), 0);
+
+            let mut request = registration_request("bob.vne", owner, 2000);
+            request.max_price = Some(2000);
+            assert_eq!(register_committed(&mut registrar, request, 2000), Ok(()));
+            assert_eq!(registrar.read_treasury_balance(), 2000);
+        }
+
+        #[ink::test]
+        fn commitment_covers_records() {
+            let registrar = new_registrar();
+            let commit = |records: Vec<RecordEntry>| {
+                registrar
+                    .make_commitment_with_records(
+                        String::from("alice.vne"),
+                        AccountId::from([0x2; 20]),
+                        2000,
+                        [0x7; 32],
+                        AccountId::from([0x3; 20]),
+                        records,
+                    )
+                    .unwrap()
+            };
+            let content_hash = RecordEntry::ContentHash(String::from("ipfs://alice"));
+
+            assert_eq!(
+                commit(Vec::new()),
+                registrar
+                    .make_commitment(
+                        String::from("alice.vne"),
+                        AccountId::from([0x2; 20]),
+                        2000,
+                        [0x7; 32],
+                        AccountId::from([0x3; 20]),
+                    )
+                    .unwrap()
+            );
+            assert_ne!(commit(vec![content_hash.clone()]), commit(Vec::new()));
+            assert_ne!(
+                commit(vec![content_hash.clone()]),
+                commit(vec![RecordEntry::ContentHash(String::from(
+                    "ipfs://mallory"
+                ))])
+            );
+            assert_eq!(
+                commit(vec![content_hash.clone()]),
+                commit(vec![content_hash])
+            );
+        }
+
+        #[ink::test]
+        fn set_call_limits_works() {
+            let mut registrar = new_registrar();
+            assert_eq!(registrar.read_call_limits(), CallLimits::default());
+            let limits = CallLimits {
+                ref_time_limit: 10_000_000_000,
+                proof_size_limit: 2_000_000,
+                storage_deposit_limit: 1_000_000,
+            };
+            assert_eq!(registrar.set_call_limits(limits), Ok(()));
+            assert_eq!(registrar.read_call_limits(), limits);
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                registrar.set_call_limits(CallLimits::default()),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+
+        #[ink::test]
+        fn register_works_with_mocked_callees() {
+            let callees = mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+
+            assert_eq!(
+                registrar.read_domain_price("alice.vne".to_string(), 2000),
+                Some(2000)
+            );
+            assert_eq!(
+                commit_and_register(&mut registrar, "alice.vne", owner, 2000, 1999),
+                Err(Error::InsufficientPayment)
+            );
+            assert_eq!(
+                commit_and_register(&mut registrar, "bob.vne", owner, 2000, 2000),
+                Ok(())
+            );
+            assert_eq!(
+                callees
+                    .borrow()
+                    .domains
+                    .get("bob.vne")
+                    .map(|domain| domain.0),
+                Some(owner)
+            );
+            assert_eq!(registrar.read_treasury_balance(), 2000);
+            assert_eq!(
+                registrar.check_domain_availablility("bob.vne".to_string()),
+                Ok(false)
+            );
+        }
+
+        #[ink::test]
+        fn commit_many_reports_each_rejected_hash() {
+            let mut registrar = new_registrar();
+
+            assert_eq!(registrar.commit_many(vec![[0x5; 32], [0x6; 32]]), Ok(()));
+            assert_eq!(
+                registrar.commit_many(vec![[0x7; 32], [0x6; 32], [0x5; 32]]),
+                Err(BatchError::Items(vec![
+                    (1, Error::UnexpiredCommitmentExists),
+                    (2, Error::UnexpiredCommitmentExists),
+                ]))
+            );
+
+            registrar.pause().unwrap();
+            assert_eq!(
+                registrar.commit_many(vec![[0x8; 32]]),
+                Err(BatchError::Batch(Error::Paused))
+            );
+        }
+
+        #[ink::test]
+        fn register_many_registers_every_name() {
+            let callees = mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            let requests = vec![
+                registration_request("alice.vne", owner, 2000),
+                registration_request("bob.vne", owner, 3000),
+            ];
+            commit_requests(&mut registrar, &requests);
+            advance_time(10);
+
+            ink::env::test::set_value_transferred::<MyEnvironment>(4999);
+            assert_eq!(
+                registrar.register_many(requests.clone()),
+                Err(BatchError::Batch(Error::InsufficientPayment))
+            );
+            // the off-chain engine does not revert the failed call's consumed commitments
+            commit_requests(&mut registrar, &requests);
+            advance_time(10);
+            ink::env::test::set_value_transferred::<MyEnvironment>(5000);
+            assert_eq!(registrar.register_many(requests), Ok(()));
+
+            let domains = &callees.borrow().domains;
+            assert_eq!(domains.get("alice.vne").map(|domain| domain.0), Some(owner));
+            assert_eq!(domains.get("bob.vne").map(|domain| domain.0), Some(owner));
+            assert_eq!(registrar.read_treasury_balance(), 5000);
+        }
+
+        #[ink::test]
+        fn register_many_reports_each_rejected_request() {
+            mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            commit_and_register(&mut registrar, "taken.vne", owner, 2000, 2000).unwrap();
+
+            let committed = vec![
+                registration_request("alice.vne", owner, 2000),
+                registration_request("taken.vne", owner, 2000),
+            ];
+            commit_requests(&mut registrar, &committed);
+            advance_time(10);
+
+            let mut requests = committed;
+            requests.push(registration_request("bob.vne", owner, 2000));
+            requests.push(registration_request("bob.evil", owner, 2000));
+            ink::env::test::set_value_transferred::<MyEnvironment>(8000);
+            assert_eq!(
+                registrar.register_many(requests),
+                Err(BatchError::Items(vec![
+                    (1, Error::AlreadyRegistered),
+                    (2, Error::CommitmentNotFound),
+                    (3, Error::UnsupportedTld),
+                ]))
+            );
+        }
+
+        #[ink::test]
+        fn register_many_reports_the_resolver_error() {
+            mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            let requests = vec![
+                registration_request("alice.vne", owner, 2000),
+                registration_request("alice.vne", owner, 3000),
+            ];
+            commit_requests(&mut registrar, &requests);
+            advance_time(10);
+
+            ink::env::test::set_value_transferred::<MyEnvironment>(5000);
+            assert_eq!(
+                registrar.register_many(requests),
+                Err(BatchError::Items(vec![(
+                    1,
+                    Error::Resolver(ResolverError::DomainAlreadyRegistered)
+                )]))
+            );
+        }
+
+        #[ink::test]
+        fn renew_many_renews_every_name() {
+            let callees = mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            commit_and_register(&mut registrar, "alice.vne", owner, 2000, 2000).unwrap();
+            commit_and_register(&mut registrar, "bob.vne", owner, 2000, 2000).unwrap();
+            let expiry_time =
+                |name: &str| callees.borrow().domains.get(name).map(|domain| domain.1);
+            let alice_expiry_time = expiry_time("alice.vne").unwrap();
+            let bob_expiry_time = expiry_time("bob.vne").unwrap();
+
+            let renewals = vec![
+                RenewalRequest {
+                    domain_name: "Alice.vne".to_string(),
+                    duration: 1000,
+                },
+                RenewalRequest {
+                    domain_name: "bob.vne".to_string(),
+                    duration: 3000,
+                },
+            ];
+            ink::env::test::set_value_transferred::<MyEnvironment>(3999);
+            assert_eq!(
+                registrar.renew_many(renewals.clone()),
+                Err(BatchError::Batch(Error::InsufficientPayment))
+            );
+            ink::env::test::set_value_transferred::<MyEnvironment>(4000);
+            assert_eq!(registrar.renew_many(renewals), Ok(()));
+            assert_eq!(expiry_time("alice.vne"), Some(alice_expiry_time + 1000));
+            assert_eq!(expiry_time("bob.vne"), Some(bob_expiry_time + 3000));
+
+            let renewals = vec![
+                RenewalRequest {
+                    domain_name: "carol.vne".to_string(),
+                    duration: 1000,
+                },
+                RenewalRequest {
+                    domain_name: "alice.vne".to_string(),
+                    duration: 1000,
+                },
+            ];
+            assert_eq!(
+                registrar.renew_many(renewals),
+                Err(BatchError::Items(vec![(0, Error::DomainNotRegistered)]))
+            );
+        }
+
+        /// Mocks the callees with `gold.vne` as a premium name and starts its auction,
+        /// with a reserve price of 20000.
+        fn start_gold_auction() -> (Rc<RefCell<Callees>>, Registrar) {
+            let callees = mock_callees();
+            callees
+                .borrow_mut()
+                .premium_names
+                .push("gold.vne".to_string());
+            let mut registrar = new_registrar();
+            let config = AuctionConfig {
+                bidding_period: 100,
+                reveal_period: 100,
+                registration_duration: 2000,
+            };
+            assert_eq!(registrar.set_auction_config(Some(config)), Ok(()));
+            assert_eq!(registrar.start_auction("gold.vne".to_string()), Ok(()));
+            (callees, registrar)
+        }
+
+        fn place_bid(
+            registrar: &mut Registrar,
+            bidder: AccountId,
+            value: Balance,
+            deposit: Balance,
+        ) {
+            set_caller(bidder);
+            let bid_hash = registrar
+                .make_bid_hash("gold.vne".to_string(), value, [0x9; 32], bidder)
+                .unwrap();
+            ink::env::test::set_value_transferred::<MyEnvironment>(deposit);
+            assert_eq!(registrar.bid("gold.vne".to_string(), bid_hash), Ok(()));
+            ink::env::test::set_value_transferred::<MyEnvironment>(0);
+        }
+
+        fn reveal_bid(registrar: &mut Registrar, bidder: AccountId, value: Balance) -> Result<()> {
+            set_caller(bidder);
+            registrar.reveal_bid("gold.vne".to_string(), value, [0x9; 32])
+        }
+
+        fn balance_of(account: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<MyEnvironment>(account).unwrap_or_default()
+        }
+
+        #[ink::test]
+        fn auction_sells_at_the_second_price() {
+            let (callees, mut registrar) = start_gold_auction();
+            let (bob, carol, dave, erin) = (
+                AccountId::from([0x5; 20]),
+                AccountId::from([0x6; 20]),
+                AccountId::from([0x7; 20]),
+                AccountId::from([0x8; 20]),
+            );
+            place_bid(&mut registrar, bob, 30000, 35000);
+            place_bid(&mut registrar, carol, 25000, 25000);
+            // below the reserve price
+            place_bid(&mut registrar, dave, 10000, 10000);
+            // above its deposit
+            place_bid(&mut registrar, erin, 50000, 40000);
+            assert_eq!(
+                reveal_bid(&mut registrar, bob, 30000),
+                Err(Error::NotInRevealPhase)
+            );
+
+            advance_time(100);
+            assert_eq!(
+                registrar.bid("gold.vne".to_string(), [0x0; 32]),
+                Err(Error::BiddingClosed)
+            );
+            assert_eq!(
+                reveal_bid(&mut registrar, carol, 20000),
+                Err(Error::InvalidReveal)
+            );
+            assert_eq!(reveal_bid(&mut registrar, carol, 25000), Ok(()));
+            assert_eq!(balance_of(carol), 0);
+            assert_eq!(reveal_bid(&mut registrar, bob, 30000), Ok(()));
+            assert_eq!(balance_of(carol), 25000);
+            assert_eq!(reveal_bid(&mut registrar, dave, 10000), Ok(()));
+            assert_eq!(balance_of(dave), 10000);
+            assert_eq!(reveal_bid(&mut registrar, erin, 50000), Ok(()));
+            assert_eq!(balance_of(erin), 40000);
+            assert_eq!(
+                registrar.finalize_auction("gold.vne".to_string()),
+                Err(Error::AuctionNotEnded)
+            );
+
+            advance_time(100);
+            assert_eq!(
+                reveal_bid(&mut registrar, bob, 30000),
+                Err(Error::NotInRevealPhase)
+            );
+            assert_eq!(registrar.finalize_auction("gold.vne".to_string()), Ok(()));
+            assert_eq!(
+                callees
+                    .borrow()
+                    .domains
+                    .get("gold.vne")
+                    .map(|domain| domain.0),
+                Some(bob)
+            );
+            assert_eq!(balance_of(bob), 10000);
+            assert_eq!(registrar.read_treasury_balance(), 25000);
+            assert_eq!(registrar.read_auction("gold.vne".to_string()), None);
+        }
+
+        #[ink::test]
+        fn auction_pays_the_reserve_price_without_a_second_bid() {
+            let (callees, mut registrar) = start_gold_auction();
+            let bob = AccountId::from([0x5; 20]);
+            let carol = AccountId::from([0x6; 20]);
+            place_bid(&mut registrar, bob, 30000, 30000);
+            place_bid(&mut registrar, carol, 40000, 40000);
+
+            // carol does not reveal and reclaims her deposit once the auction is over
+            advance_time(100);
+            assert_eq!(reveal_bid(&mut registrar, bob, 30000), Ok(()));
+            advance_time(100);
+            let auction_id = registrar.read_auction("gold.vne".to_string()).unwrap().id;
+            set_caller(carol);
+            assert_eq!(registrar.reclaim_bid(auction_id), Ok(()));
+            assert_eq!(balance_of(carol), 40000);
+
+            assert_eq!(registrar.finalize_auction("gold.vne".to_string()), Ok(()));
+            assert_eq!(
+                callees
+                    .borrow()
+                    .domains
+                    .get("gold.vne")
+                    .map(|domain| domain.0),
+                Some(bob)
+            );
+            assert_eq!(balance_of(bob), 10000);
+            assert_eq!(registrar.read_treasury_balance(), 20000);
+        }
+
+        #[ink::test]
+        fn auction_without_valid_bids_has_no_winner() {
+            let (callees, mut registrar) = start_gold_auction();
+            let bob = AccountId::from([0x5; 20]);
+            place_bid(&mut registrar, bob, 10000, 10000);
+            advance_time(100);
+            assert_eq!(reveal_bid(&mut registrar, bob, 10000), Ok(()));
+            advance_time(100);
+
+            assert_eq!(registrar.finalize_auction("gold.vne".to_string()), Ok(()));
+            assert!(callees.borrow().domains.is_empty());
+            assert_eq!(balance_of(bob), 10000);
+            assert_eq!(registrar.read_treasury_balance(), 0);
+        }
+
+        #[ink::test]
+        fn finalize_refunds_the_winner_of_a_taken_name() {
+            let (callees, mut registrar) = start_gold_auction();
+            let bob = AccountId::from([0x5; 20]);
+            place_bid(&mut registrar, bob, 30000, 35000);
+            advance_time(100);
+            assert_eq!(reveal_bid(&mut registrar, bob, 30000), Ok(()));
+            advance_time(100);
+
+            let owner = AccountId::from([0x6; 20]);
+            callees
+                .borrow_mut()
+                .domains
+                .insert("gold.vne".to_string(), (owner, 0));
+            assert_eq!(registrar.finalize_auction("gold.vne".to_string()), Ok(()));
+            assert_eq!(
+                callees
+                    .borrow()
+                    .domains
+                    .get("gold.vne")
+                    .map(|domain| domain.0),
+                Some(owner)
+            );
+            assert_eq!(balance_of(bob), 35000);
+            assert_eq!(registrar.read_treasury_balance(), 0);
+        }
+
+        #[ink::test]
+        fn premium_names_require_an_auction() {
+            let (callees, mut registrar) = start_gold_auction();
+            let owner = AccountId::from([0x5; 20]);
+            assert_eq!(
+                commit_and_register(&mut registrar, "gold.vne", owner, 2000, 20000),
+                Err(Error::AuctionRequired)
+            );
+            assert_eq!(
+                registrar.start_auction("gold.vne".to_string()),
+                Err(Error::NameInAuction)
+            );
+
+            callees
+                .borrow_mut()
+                .premium_names
+                .push("silver.vne".to_string());
+            assert_eq!(
+                commit_and_register(&mut registrar, "silver.vne", owner, 2000, 20000),
+                Err(Error::AuctionRequired)
+            );
+            assert_eq!(registrar.set_auction_config(None), Ok(()));
+            assert_eq!(
+                commit_and_register(&mut registrar, "silver.vne", owner, 3000, 30000),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn renew_charges_any_payer() {
+            let callees = mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            commit_and_register(&mut registrar, "alice.vne", owner, 2000, 2000).unwrap();
+            let expiry_time = callees.borrow().domains["alice.vne"].1;
+
+            let friend = AccountId::from([0x6; 20]);
+            set_caller(friend);
+            ink::env::test::set_value_transferred::<MyEnvironment>(999);
+            assert_eq!(
+                registrar.renew("alice.vne".to_string(), 1000),
+                Err(Error::InsufficientPayment)
+            );
+            ink::env::test::set_value_transferred::<MyEnvironment>(1500);
+            assert_eq!(
+                registrar.renew("bob.vne".to_string(), 1000),
+                Err(Error::DomainNotRegistered)
+            );
+            assert_eq!(registrar.renew("Alice.vne".to_string(), 1000), Ok(()));
+
+            assert_eq!(
+                callees.borrow().domains["alice.vne"],
+                (owner, expiry_time + 1000)
+            );
+            assert_eq!(registrar.read_treasury_balance(), 3000);
+            assert_eq!(balance_of(friend), 500);
+        }
+
+        #[ink::test]
+        fn register_reports_failing_callees() {
+            mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+
+            // the resolver traps while writing the record
+            mock_callee(RESOLVER, |selector, _| match selector {
+                ink::selector_bytes!("check_domain_availablility") => answer(true),
+                _ => trap(),
+            });
+            assert_eq!(
+                commit_and_register(&mut registrar, "alice.vne", owner, 2000, 2000),
+                Err(Error::Call(CallError::Trapped))
+            );
+
+            // the resolver is an older contract without the availability check
+            mock_callee(RESOLVER, |_, _| unknown_message());
+            assert_eq!(
+                commit_and_register(&mut registrar, "bob.vne", owner, 2000, 2000),
+                Err(Error::Call(CallError::UnknownMessage))
+            );
+
+            // the price oracle is gone
+            mock_callees();
+            unmock_callee(PRICE_ORACLE);
+            assert_eq!(
+                registrar.read_domain_price("carol.vne".to_string(), 2000),
+                None
+            );
+            assert_eq!(
+                commit_and_register(&mut registrar, "carol.vne", owner, 2000, 2000),
+                Err(Error::Call(CallError::NotCallable))
+            );
+
+            // the price oracle answers with something else than a quote
+            mock_callee(PRICE_ORACLE, |_, _| answer(true));
+            assert_eq!(
+                commit_and_register(&mut registrar, "dave.vne", owner, 2000, 2000),
+                Err(Error::Call(CallError::Decode))
+            );
+        }
+
+        #[ink::test]
+        fn renew_reports_failing_resolver() {
+            mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            assert_eq!(
+                commit_and_register(&mut registrar, "alice.vne", owner, 2000, 2000),
+                Ok(())
+            );
+
+            mock_callee(RESOLVER, |selector, _| match selector {
+                ink::selector_bytes!("check_domain_availablility") => answer(false),
+                _ => trap(),
+            });
+            ink::env::test::set_value_transferred::<MyEnvironment>(2000);
+            assert_eq!(
+                registrar.renew("alice.vne".to_string(), 2000),
+                Err(Error::Call(CallError::Trapped))
+            );
+        }
+
+        #[ink::test]
+        fn mint_nft_mints_to_the_domain_owner() {
+            let callees = mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            assert_eq!(
+                registrar.mint_nft("alice.vne".to_string(), "uri".to_string()),
+                Err(Error::DomainNotRegistered)
+            );
+            commit_and_register(&mut registrar, "alice.vne", owner, 2000, 2000).unwrap();
+
+            set_caller(owner);
+            assert_eq!(
+                registrar.mint_nft("alice.vne".to_string(), "uri".to_string()),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+            set_caller(AccountId::from([0x1; 20]));
+            assert_eq!(
+                registrar.mint_nft("Alice.vne".to_string(), "uri".to_string()),
+                Ok(())
+            );
+            assert_eq!(
+                callees.borrow().minted,
+                vec![(1, "alice.vne".to_string(), owner)]
+            );
+            assert_eq!(
+                registrar.read_domain_token_id("ALICE.vne".to_string()),
+                Some(1)
+            );
+            assert_eq!(
+                registrar.mint_nft("alice.vne".to_string(), "uri".to_string()),
+                Err(Error::NftAlreadyMinted)
+            );
+        }
+
+        #[ink::test]
+        fn mint_reports_failing_nft() {
+            mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            assert_eq!(
+                commit_and_register(&mut registrar, "alice.vne", owner, 2000, 2000),
+                Ok(())
+            );
+
+            mock_callee(NFT, |_, _| trap());
+            assert_eq!(
+                registrar.mint_nft("alice.vne".to_string(), "uri".to_string()),
+                Err(Error::Call(CallError::Trapped))
+            );
+            assert_eq!(
+                registrar.read_domain_token_id("alice.vne".to_string()),
+                None
+            );
+
+            mock_callee(NFT, |_, _| answer(Err::<(), _>(NftError::TokenExists)));
+            assert_eq!(
+                registrar.mint_nft("alice.vne".to_string(), "uri".to_string()),
+                Err(Error::Nft(NftError::TokenExists))
+            );
+        }
+
+        #[ink::test]
+        fn register_with_token_reports_token_errors() {
+            mock_callees();
+            let mut registrar = new_registrar();
+            let owner = AccountId::from([0x5; 20]);
+            let token = AccountId::from([0x6; 20]);
+            registrar.add_payment_token(token).unwrap();
+            mock_callee(token, |selector, _| match selector {
+                ink::selector_bytes!("PSP22::transfer_from") => {
+                    answer(Err::<(), _>(PSP22Error::Custom(String::from("frozen"))))
+                }
+                _ => unknown_message(),
+            });
+
+            let request = registration_request("alice.vne", owner, 2000);
+            commit_requests(&mut registrar, std::slice::from_ref(&request));
+            advance_time(10);
+            assert_eq!(
+                registrar.register_with_token(request, token),
+                Err(Error::Token(PSP22Error::Custom(String::from("frozen"))))
+            );
+        }
+
+        #[ink::test]
+        fn trait_selectors_are_pinned() {
+            use scale::Encode;
+            fn selector<Args: scale::Encode, R>(
+                params: ink::env::call::CallParams<
+                    MyEnvironment,
+                    ink::env::call::Call<MyEnvironment>,
+                    Args,
+                    R,
+                >,
+            ) -> [u8; 4] {
+                let input = params.exec_input().encode();
+                [input[0], input[1], input[2], input[3]]
+            }
+
+            // contracts deployed before the traits existed answer to the selectors
+            // ink derived from the bare message names
+            let registrar = new_registrar();
+            let mut resolver = registrar.resolver();
+            let name = String::from("alice");
+            let account = AccountId::from([0x2; 20]);
+            assert_eq!(
+                selector(
+                    resolver
+                        .call_mut()
+                        .set_record([0; 32], name.clone(), account, 1, [0; 32], account, 1)
+                        .params()
+                ),
+                ink::selector_bytes!("set_record")
+            );
+            assert_eq!(
+                selector(
+                    resolver
+                        .call_mut()
+                        .set_records(name.clone(), Vec::new())
+                        .params()
+                ),
+                ink::selector_bytes!("set_records")
+            );
+            assert_eq!(
+                selector(resolver.call_mut().renew_domain(name.clone(), 1).params()),
+                ink::selector_bytes!("renew_domain")
+            );
+            assert_eq!(
+                selector(
+                    resolver
+                        .call_mut()
+                        .register_subdomain(name.clone(), name.clone())
+                        .params()
+                ),
+                ink::selector_bytes!("register_subdomain")
+            );
+            assert_eq!(
+                selector(resolver.call().read_grace_period().params()),
+                ink::selector_bytes!("read_grace_period")
+            );
+            assert_eq!(
+                selector(resolver.call().read_content_hash(name.clone()).params()),
+                ink::selector_bytes!("read_content_hash")
+            );
+            assert_eq!(
+                selector(resolver.call().read_domain_owner(name.clone()).params()),
+                ink::selector_bytes!("read_domain_owner")
+            );
+            assert_eq!(
+                selector(
+                    resolver
+                        .call()
+                        .read_domain_expiry_time(name.clone())
+                        .params()
+                ),
+                ink::selector_bytes!("read_domain_expiry_time")
+            );
+            assert_eq!(
+                selector(
+                    resolver
+                        .call()
+                        .check_domain_availablility(name.clone())
+                        .params()
+                ),
+                ink::selector_bytes!("check_domain_availablility")
+            );
+
+            let mut price_oracle = registrar.price_oracle();
+            assert_eq!(
+                selector(price_oracle.call().quote(name.clone(), 1).params()),
+                ink::selector_bytes!("quote")
+            );
+            assert_eq!(
+                selector(price_oracle.call().convert_to_token(account, 1).params()),
+                ink::selector_bytes!("convert_to_token")
+            );
+            assert_eq!(
+                selector(price_oracle.call().is_premium_name(name.clone()).params()),
+                ink::selector_bytes!("is_premium_name")
+            );
+            assert_eq!(
+                selector(
+                    price_oracle
+                        .call_mut()
+                        .record_release(name.clone(), 1)
+                        .params()
+                ),
+                ink::selector_bytes!("record_release")
+            );
+
+            let mut nft: contract_ref!(VnsNft, MyEnvironment) =
+                FromAccountId::from_account_id(account);
+            assert_eq!(
+                selector(nft.call_mut().mint(1, name.clone(), account, name).params()),
+                ink::selector_bytes!("mint")
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::ContractsBackend;
+        use nft::erc721::{Erc721, Erc721Ref};
+        use price_oracle::priceoracle::{Priceoracle, PriceoracleRef, TokenRate};
+        use psp22_mock::psp22_mock::{Psp22Mock, Psp22MockRef, PSP22};
+        use resolver::resolver::{Record, RecordRef};
+        use vns_common::access::MANAGER;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+        type E2EClient = ink_e2e::Client<ink_e2e::PolkadotConfig, MyEnvironment>;
+
+        /// The accounts of the contracts `deploy` instantiates.
+        struct Deployment {
+            registrar: AccountId,
+            token: AccountId,
+        }
+
+        /// The account of `keypair`, the first 20 bytes of its public key as in the
+        /// unit tests.
+        fn account_of(keypair: &ink_e2e::Keypair) -> AccountId {
+            let mut account = [0u8; 20];
+            account.copy_from_slice(&keypair.public_key().0[..20]);
+            account
+        }
+
+        /// Deploys the registrar with its resolver, price oracle and NFT, all
+        /// administered by alice, and a PSP22 token accepted for payments at one
+        /// token per native unit. Alice holds 10^18 of the token. The registrar
+        /// calls `price_oracle` instead of the deployed oracle if set.
+        async fn deploy(client: &mut E2EClient, price_oracle: Option<AccountId>) -> Deployment {
+            let alice = account_of(&ink_e2e::alice());
+
+            let mut constructor = RecordRef::new(alice, alice, 1000);
+            let resolver = client
+                .instantiate("resolver", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate resolver failed")
+                .account_id;
+            let mut constructor = PriceoracleRef::new(alice);
+            let deployed_oracle = client
+                .instantiate("price_oracle", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate price oracle failed")
+                .account_id;
+            let mut constructor = Erc721Ref::new(resolver);
+            let nft = client
+                .instantiate("nft", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate nft failed")
+                .account_id;
+            let mut constructor = Psp22MockRef::new();
+            let token = client
+                .instantiate("psp22_mock", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate token failed")
+                .account_id;
+
+            let price_oracle = price_oracle.unwrap_or(deployed_oracle);
+            let mut constructor =
+                RegistrarRef::new(alice, 600_000, 0, 1000, resolver, price_oracle, nft);
+            let registrar = client
+                .instantiate("registrar", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate registrar failed")
+                .account_id;
+
+            // the registrar writes records and mints as a manager of the resolver and NFT
+            let mut resolver_ref = ink_e2e::create_call_builder::<Record>(resolver);
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &resolver_ref.grant_role(MANAGER, registrar),
+                )
+                .submit()
+                .await
+                .expect("grant resolver role failed");
+            let mut nft_ref = ink_e2e::create_call_builder::<Erc721>(nft);
+            client
+                .call(&ink_e2e::alice(), &nft_ref.grant_role(MANAGER, registrar))
+                .submit()
+                .await
+                .expect("grant nft role failed");
+
+            let mut oracle_ref = ink_e2e::create_call_builder::<Priceoracle>(deployed_oracle);
+            client
+                .call(&ink_e2e::alice(), &oracle_ref.report_exchange_rate(100))
+                .submit()
+                .await
+                .expect("report exchange rate failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &oracle_ref.set_token_rate(
+                        token,
+                        Some(TokenRate {
+                            tokens: 1,
+                            native: 1,
+                        }),
+                    ),
+                )
+                .submit()
+                .await
+                .expect("set token rate failed");
+
+            let mut token_ref = ink_e2e::create_call_builder::<Psp22Mock>(token);
+            client
+                .call(&ink_e2e::alice(), &token_ref.mint(alice, 10u128.pow(18)))
+                .submit()
+                .await
+                .expect("mint failed");
+            let mut registrar_ref = ink_e2e::create_call_builder::<Registrar>(registrar);
+            client
+                .call(&ink_e2e::alice(), &registrar_ref.add_payment_token(token))
+                .submit()
+                .await
+                .expect("add payment token failed");
+
+            Deployment { registrar, token }
+        }
+
+        /// A registration request of `domain_name` to alice, and commits to it.
+        async fn commit(
+            client: &mut E2EClient,
+            registrar: AccountId,
+            domain_name: &str,
+        ) -> RegistrationRequest {
+            let alice = account_of(&ink_e2e::alice());
+            let request = RegistrationRequest {
+                domain_name: String::from(domain_name),
+                domain_owner: alice,
+                duration: 365 * 24 * 60 * 60,
+                secret: [0x7; 32],
+                resolver: alice,
+                token_uri: None,
+                referrer: None,
+                allowlist_proof: None,
+                voucher: None,
+                max_price: None,
+                records: Vec::new(),
+            };
+
+            let mut registrar_ref = ink_e2e::create_call_builder::<Registrar>(registrar);
+            let commit_hash = client
+                .call(
+                    &ink_e2e::alice(),
+                    &registrar_ref.make_commitment(
+                        request.domain_name.clone(),
+                        request.domain_owner,
+                        request.duration,
+                        request.secret,
+                        request.resolver,
+                    ),
+                )
+                .dry_run()
+                .await
+                .expect("make commitment failed")
+                .return_value()
+                .expect("invalid commitment");
+            client
+                .call(&ink_e2e::alice(), &registrar_ref.commit(commit_hash))
+                .submit()
+                .await
+                .expect("commit failed");
+            request
+        }
+
+        #[ink_e2e::test(environment = crate::registrar::MyEnvironment)]
+        async fn register_with_token_reports_a_trapping_token<Client: E2EBackend<MyEnvironment>>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let deployment = deploy(&mut client, None).await;
+            let mut token_ref = ink_e2e::create_call_builder::<Psp22Mock>(deployment.token);
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_ref.approve(deployment.registrar, 10u128.pow(18)),
+                )
+                .submit()
+                .await
+                .expect("approve failed");
+            client
+                .call(&ink_e2e::alice(), &token_ref.set_trap_transfers(true))
+                .submit()
+                .await
+                .expect("set trap transfers failed");
+
+            let request = commit(&mut client, deployment.registrar, "alice.vne").await;
+            let mut registrar_ref = ink_e2e::create_call_builder::<Registrar>(deployment.registrar);
+            let register = registrar_ref.register_with_token(request, deployment.token);
+            let result = client.call(&ink_e2e::alice(), &register).dry_run().await?;
+            assert_eq!(result.return_value(), Err(Error::Call(CallError::Trapped)));
+            Ok(())
+        }
+
+        #[ink_e2e::test(environment = crate::registrar::MyEnvironment)]
+        async fn price_quotes_report_a_missing_price_oracle<Client: E2EBackend<MyEnvironment>>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            // no contract lives at the price oracle address
+            let deployment = deploy(&mut client, Some([0x7; 20])).await;
+
+            let registrar_ref = ink_e2e::create_call_builder::<Registrar>(deployment.registrar);
+            let read_price_quote =
+                registrar_ref.read_price_quote(String::from("alice.vne"), 365 * 24 * 60 * 60);
+            let result = client
+                .call(&ink_e2e::alice(), &read_price_quote)
+                .dry_run()
+                .await?;
+            assert_eq!(
+                result.return_value(),
+                Err(Error::Call(CallError::NotCallable))
+            );
+            Ok(())
+        }
+
+        /// The token balance of `account`.
+        async fn token_balance(
+            client: &mut E2EClient,
+            token: AccountId,
+            account: AccountId,
+        ) -> Balance {
+            let token_ref = ink_e2e::create_call_builder::<Psp22Mock>(token);
+            client
+                .call(&ink_e2e::alice(), &token_ref.balance_of(account))
+                .dry_run()
+                .await
+                .expect("balance of failed")
+                .return_value()
+        }
+
+        #[ink_e2e::test(environment = crate::registrar::MyEnvironment)]
+        async fn token_payments_reach_the_treasury<Client: E2EBackend<MyEnvironment>>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let alice = account_of(&ink_e2e::alice());
+            let bob = account_of(&ink_e2e::bob());
+            let deployment = deploy(&mut client, None).await;
+            let (registrar, token) = (deployment.registrar, deployment.token);
+            let mut registrar_ref = ink_e2e::create_call_builder::<Registrar>(registrar);
+            let mut token_ref = ink_e2e::create_call_builder::<Psp22Mock>(token);
+            let fee_split = FeeSplit {
+                referrer_bps: 1000,
+                partner_bps: 0,
+                partner: None,
+            };
+            client
+                .call(&ink_e2e::alice(), &registrar_ref.set_fee_split(fee_split))
+                .submit()
+                .await
+                .expect("set fee split failed");
+
+            // nothing is collected without an approval
+            let mut request = commit(&mut client, registrar, "alice.vne").await;
+            request.referrer = Some(bob);
+            let register = registrar_ref.register_with_token(request.clone(), token);
+            let result = client.call(&ink_e2e::alice(), &register).dry_run().await?;
+            assert_eq!(
+                result.return_value(),
+                Err(Error::Token(PSP22Error::InsufficientAllowance))
+            );
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_ref.approve(registrar, 10u128.pow(18)),
+                )
+                .submit()
+                .await
+                .expect("approve failed");
+            let result = client
+                .call(&ink_e2e::alice(), &register)
+                .submit()
+                .await
+                .expect("register with token failed");
+            assert_eq!(result.return_value(), Ok(()));
+            let registration_fee = token_balance(&mut client, token, registrar).await;
+            assert!(registration_fee > 0);
+            assert_eq!(
+                token_balance(&mut client, token, alice).await,
+                10u128.pow(18) - registration_fee
+            );
+
+            // the referrer's share is theirs to withdraw
+            let referral = registration_fee / 10;
+            let result = client
+                .call(
+                    &ink_e2e::alice(),
+                    &registrar_ref.read_token_balance(token, bob),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(result.return_value(), referral);
+            let result = client
+                .call(&ink_e2e::bob(), &registrar_ref.withdraw_token(token))
+                .submit()
+                .await
+                .expect("withdraw token failed");
+            assert_eq!(result.return_value(), Ok(()));
+            assert_eq!(token_balance(&mut client, token, bob).await, referral);
+
+            let renew =
+                registrar_ref.renew_with_token(String::from("alice.vne"), request.duration, token);
+            let result = client
+                .call(&ink_e2e::alice(), &renew)
+                .submit()
+                .await
+                .expect("renew with token failed");
+            assert_eq!(result.return_value(), Ok(()));
+            let collected = token_balance(&mut client, token, registrar).await;
+            assert!(collected > registration_fee - referral);
+
+            // renewals earn no referral
+            let result = client
+                .call(&ink_e2e::alice(), &registrar_ref.read_token_treasury(token))
+                .dry_run()
+                .await?;
+            assert_eq!(result.return_value(), collected);
+            let withdraw = registrar_ref.withdraw_treasury_token(token, collected, alice);
+            let result = client
+                .call(&ink_e2e::alice(), &withdraw)
+                .submit()
+                .await
+                .expect("withdraw treasury token failed");
+            assert_eq!(result.return_value(), Ok(()));
+            assert_eq!(token_balance(&mut client, token, registrar).await, 0);
+            assert_eq!(
+                token_balance(&mut client, token, alice).await,
+                10u128.pow(18) - referral
+            );
+            Ok(())
         }
     }
 }
@@ -181,9 +181,10 @@ async function main() {
         }
     }
 
-    async function register_domain(domain_name, domain_owner, duration, commit_hash, resolver) {
+    // request: { domainName, domainOwner, duration, secret, resolver, tokenUri, referrer, allowlistProof, voucher, maxPrice, records }
+    async function register_domain(request) {
         await contract.tx
-            .register({ value:  240253678335870n, storageDepositLimit, gasLimit }, domain_name, domain_owner, duration, commit_hash, resolver)
+            .register({ value:  240253678335870n, storageDepositLimit, gasLimit }, request)
             .signAndSend(userKeyring, result => {
                 if (result.status.isInBlock) {
                     console.log(`initialised in block : ${result.status.asInBlock}`.cyan);
@@ -227,7 +228,7 @@ async function main() {
     await read_domain_price("arpitssk.vne", 400000); 
     // await make_commitment("arpitssk.vne", userKeyring.address, 400000, "0x01cda9526241efc47b98941546f244a0c9971873278214c59966241d2d667397","0xA62638e3931f800b924d5648A0562532f5b26CF3");
     // await commit("0x8f7c07641628a6e7cc1871a3e8cff4c282695987807d4241f90c8c8954520b89");
-    // await register_domain("arpitssk.vne", userKeyring.address, 400000, "0x01cda9526241efc47b98941546f244a0c9971873278214c59966241d2d667397", "0xA62638e3931f800b924d5648A0562532f5b26CF3"); 
+    // await register_domain({ domainName: "arpitssk.vne", domainOwner: userKeyring.address, duration: 400000, secret: "0x01cda9526241efc47b98941546f244a0c9971873278214c59966241d2d667397", resolver: "0xA62638e3931f800b924d5648A0562532f5b26CF3", tokenUri: null, referrer: null, allowlistProof: null, voucher: null, maxPrice: null, records: [] }); 
 
 }
 
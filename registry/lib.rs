@@ -3,80 +3,240 @@
 #[ink::contract(env = MyEnvironment)]
 mod registry {
 
-    #[derive(Clone)]
-    pub struct MyEnvironment;
-
-    impl ink::env::Environment for MyEnvironment {
-        const MAX_EVENT_TOPICS: usize = 3;
-        type AccountId = [u8; 20];
-        type Balance = u128;
-        type Hash = [u8; 32];
-        type Timestamp = u64;
-        type BlockNumber = u32;
-        type ChainExtension = ::ink::env::NoChainExtension;
-    }
+    pub type MyEnvironment = vns_common::VnsEnvironment;
 
-    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::codegen::TraitCallBuilder;
+    use ink::contract_ref;
+    use ink::env::call::FromAccountId;
     // use ink::env::DefaultEnvironment;
     use ink::prelude::string::String;
+    use vns_common::access::{AccessControl, AccessError, ADMIN};
+    use vns_common::call::{self, CallError, CallLimits, CallLimitsSet};
+    use vns_common::resolver::{ResolverError, VnsResolver};
 
     #[ink(storage)]
     pub struct Registry {
         resolver_contract_address: AccountId,
+        call_limits: CallLimits,
+        access: AccessControl,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// The call to the resolver failed.
+        Call(CallError),
+        /// The resolver rejected the read, e.g. because the domain is not registered.
+        Resolver(ResolverError),
+        Access(AccessError),
+    }
+
+    impl From<CallError> for Error {
+        fn from(error: CallError) -> Self {
+            Error::Call(error)
+        }
     }
 
+    impl From<ResolverError> for Error {
+        fn from(error: ResolverError) -> Self {
+            Error::Resolver(error)
+        }
+    }
+
+    impl From<AccessError> for Error {
+        fn from(error: AccessError) -> Self {
+            Error::Access(error)
+        }
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
     impl Registry {
+        /// The deployer becomes the admin, who may change the call limits later.
         #[ink(constructor)]
-        pub fn new(resolver_contract_address: AccountId) -> Self {
+        pub fn new(resolver_contract_address: AccountId, call_limits: CallLimits) -> Self {
             Self {
                 resolver_contract_address,
+                call_limits,
+                access: AccessControl::new(Self::env().caller(), &[]),
             }
         }
 
         #[ink(message)]
-        pub fn read_owner(&self, domain_name: String) -> AccountId {
-            build_call::<MyEnvironment>()
-                .call(AccountId::from(self.resolver_contract_address))
-                .call_v1()
-                .gas_limit(0)
-                .transferred_value(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("read_owner")))
-                        .push_arg(domain_name),
-                )
-                .returns::<AccountId>()
-                .invoke()
+        pub fn read_owner(&self, domain_name: String) -> Result<AccountId> {
+            let resolver = self.resolver();
+            Ok(call::invoke(
+                resolver.call().read_domain_owner(domain_name),
+                self.call_limits,
+            )??)
+        }
+
+        #[ink(message)]
+        pub fn read_expiry_time(&self, domain_name: String) -> Result<Timestamp> {
+            let resolver = self.resolver();
+            Ok(call::invoke(
+                resolver.call().read_domain_expiry_time(domain_name),
+                self.call_limits,
+            )??)
+        }
+
+        #[ink(message)]
+        pub fn read_content_hash(&self, domain_name: String) -> Result<String> {
+            let resolver = self.resolver();
+            Ok(call::invoke(
+                resolver.call().read_content_hash(domain_name),
+                self.call_limits,
+            )??)
+        }
+
+        /// Sets the limits of the calls to the resolver. Only the admin can set them.
+        #[ink(message)]
+        pub fn set_call_limits(&mut self, call_limits: CallLimits) -> Result<()> {
+            self.access.ensure_role(ADMIN, self.env().caller())?;
+            self.call_limits = call_limits;
+            self.env().emit_event(CallLimitsSet {
+                ref_time_limit: call_limits.ref_time_limit,
+                proof_size_limit: call_limits.proof_size_limit,
+                storage_deposit_limit: call_limits.storage_deposit_limit,
+            });
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn read_expiry_time(&self, domain_name: String) -> Timestamp {
-            build_call::<MyEnvironment>()
-                .call(AccountId::from(self.resolver_contract_address))
-                .call_v1()
-                .gas_limit(0)
-                .transferred_value(0)
-                .exec_input(
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("read_expiry_time")))
-                        .push_arg(domain_name),
-                )
-                .returns::<Timestamp>()
-                .invoke()
+        pub fn read_call_limits(&self) -> CallLimits {
+            self.call_limits
         }
+
         #[ink(message)]
-        pub fn read_content_hash(&self, domain_name:String) -> String{
-            build_call::<MyEnvironment>()
-            .call(AccountId::from(self.resolver_contract_address))
-            .call_v1()
-            .gas_limit(0)
-            .transferred_value(0)
-            .exec_input(
-                ExecutionInput::new(Selector::new(ink::selector_bytes!("read_content_hash")))
-                    .push_arg(domain_name),
-            )
-            .returns::<String>()
-            .invoke()
+        pub fn read_admin(&self) -> AccountId {
+            self.access.admin()
         }
 
+        fn resolver(&self) -> contract_ref!(VnsResolver, MyEnvironment) {
+            FromAccountId::from_account_id(self.resolver_contract_address)
+        }
     }
-}
 
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use vns_common::testing::set_caller;
+
+        #[ink::test]
+        fn set_call_limits_works() {
+            let mut registry = Registry::new(AccountId::from([0x2; 20]), CallLimits::default());
+            assert_eq!(registry.read_admin(), AccountId::from([0x1; 20]));
+            let limits = CallLimits {
+                ref_time_limit: 5_000_000_000,
+                proof_size_limit: 1_000_000,
+                storage_deposit_limit: 0,
+            };
+            assert_eq!(registry.set_call_limits(limits), Ok(()));
+            assert_eq!(registry.read_call_limits(), limits);
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+
+            set_caller(AccountId::from([0x9; 20]));
+            assert_eq!(
+                registry.set_call_limits(CallLimits::default()),
+                Err(Error::Access(AccessError::MissingRole { role: ADMIN }))
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::ContractsBackend;
+        use resolver::resolver::RecordRef;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test(environment = crate::registry::MyEnvironment)]
+        async fn reads_report_a_missing_resolver<Client: E2EBackend<MyEnvironment>>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            // no contract lives at the resolver address
+            let mut constructor = RegistryRef::new([0x7; 20], CallLimits::default());
+            let registry = client
+                .instantiate("registry", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let call_builder = registry.call_builder::<Registry>();
+
+            let read_owner = call_builder.read_owner(String::from("alice.vne"));
+            let result = client
+                .call(&ink_e2e::alice(), &read_owner)
+                .dry_run()
+                .await?;
+            assert_eq!(
+                result.return_value(),
+                Err(Error::Call(CallError::NotCallable))
+            );
+            Ok(())
+        }
+
+        #[ink_e2e::test(environment = crate::registry::MyEnvironment)]
+        async fn reads_report_a_resolver_without_the_message<Client: E2EBackend<MyEnvironment>>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            // the "resolver" is another registry, which has none of the resolver's messages
+            let mut constructor = RegistryRef::new([0x7; 20], CallLimits::default());
+            let callee = client
+                .instantiate("registry", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let mut constructor = RegistryRef::new(callee.account_id, CallLimits::default());
+            let registry = client
+                .instantiate("registry", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let call_builder = registry.call_builder::<Registry>();
+
+            let read_expiry_time = call_builder.read_expiry_time(String::from("alice.vne"));
+            let result = client
+                .call(&ink_e2e::alice(), &read_expiry_time)
+                .dry_run()
+                .await?;
+            assert_eq!(
+                result.return_value(),
+                Err(Error::Call(CallError::UnknownMessage))
+            );
+            Ok(())
+        }
+
+        #[ink_e2e::test(environment = crate::registry::MyEnvironment)]
+        async fn reads_report_an_unregistered_domain<Client: E2EBackend<MyEnvironment>>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut alice = [0u8; 20];
+            alice.copy_from_slice(&ink_e2e::alice().public_key().0[..20]);
+            let mut constructor = RecordRef::new(alice, alice, 1000);
+            let resolver = client
+                .instantiate("resolver", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate resolver failed");
+            let mut constructor = RegistryRef::new(resolver.account_id, CallLimits::default());
+            let registry = client
+                .instantiate("registry", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let call_builder = registry.call_builder::<Registry>();
+
+            let read_owner = call_builder.read_owner(String::from("alice.vne"));
+            let result = client
+                .call(&ink_e2e::alice(), &read_owner)
+                .dry_run()
+                .await?;
+            assert_eq!(
+                result.return_value(),
+                Err(Error::Resolver(ResolverError::DomainNotRegistered))
+            );
+            Ok(())
+        }
+    }
+}
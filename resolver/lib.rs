@@ -2,25 +2,27 @@
 
 #[ink::contract(env = MyEnvironment)]
 pub mod resolver {
-    #[derive(Clone)]
-    pub struct MyEnvironment;
-
-    impl ink::env::Environment for MyEnvironment {
-        const MAX_EVENT_TOPICS: usize = 3;
-        type AccountId = [u8; 20];
-        type Balance = u128;
-        type Hash = [u8; 32];
-        type Timestamp = u64;
-        type BlockNumber = u32;
-        type ChainExtension = ::ink::env::NoChainExtension;
-    }
+    pub type MyEnvironment = vns_common::VnsEnvironment;
 
     use core::ops::Add;
+    use ink::codegen::TraitCallBuilder;
+    use ink::contract_ref;
+    use ink::env::call::FromAccountId;
     use ink::env::hash::{HashOutput, Sha2x256};
     use ink::prelude::string::String;
     use ink::prelude::vec;
     use ink::prelude::vec::Vec;
+    use ink::storage::traits::StorageKey;
     use ink::storage::Mapping;
+    use vns_common::access::{
+        AccessControl, AdminTransferProposed, AdminTransferred, Role, RoleGranted, RoleRevoked,
+        ADMIN, MANAGER, PAUSER,
+    };
+    use vns_common::call::{self, CallLimits};
+    use vns_common::name;
+    use vns_common::price::VnsPriceOracle;
+    use vns_common::record::RecordEntry;
+    use vns_common::resolver::{ResolverError, VnsResolver};
 
     #[ink(storage)]
     pub struct Record {
@@ -28,9 +30,11 @@ pub mod resolver {
         domain_content_text: Mapping<String, ContentText>,
         sub_domain_content_text: Mapping<String, SubDomainContentText>,
         sub_domain_manager: Mapping<String, AccountId>,
-        admin: AccountId,
-        manager: AccountId,
+        access: AccessControl,
         grace_period: Timestamp,
+        price_oracle: Option<AccountId>,
+        paused: bool,
+        storage_version: u32,
     }
 
     #[derive(scale::Decode, scale::Encode, Debug, Clone)]
@@ -46,6 +50,36 @@ pub mod resolver {
         resolver: AccountId,
         domain_expiry_time: Timestamp,
         sub_domain: String,
+        /// The block the domain was registered in, zero for domains registered
+        /// before version 2.
+        registered_at: BlockNumber,
+    }
+
+    /// `Records` as stored by version 1, read until `migrate` converts them.
+    #[derive(scale::Decode, scale::Encode)]
+    struct RecordsV1 {
+        domain_name: String,
+        domain_owner: AccountId,
+        duration: Timestamp,
+        secret: [u8; 32],
+        resolver: AccountId,
+        domain_expiry_time: Timestamp,
+        sub_domain: String,
+    }
+
+    impl From<RecordsV1> for Records {
+        fn from(record: RecordsV1) -> Self {
+            Records {
+                domain_name: record.domain_name,
+                domain_owner: record.domain_owner,
+                duration: record.duration,
+                secret: record.secret,
+                resolver: record.resolver,
+                domain_expiry_time: record.domain_expiry_time,
+                sub_domain: record.sub_domain,
+                registered_at: 0,
+            }
+        }
     }
 
     #[derive(scale::Decode, scale::Encode, Debug, Clone)]
@@ -111,6 +145,27 @@ pub mod resolver {
         records_availability: bool,
     }
 
+    #[ink(event)]
+    pub struct Upgraded {
+        code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct Migrated {
+        from_version: u32,
+        to_version: u32,
+    }
+
+    #[ink(event)]
+    pub struct Paused {
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Unpaused {
+        account: AccountId,
+    }
+
     #[ink(event)]
     pub struct RenewDomainInfo {
         domain_name: String,
@@ -118,63 +173,33 @@ pub mod resolver {
         domain_duration: Timestamp,
     }
 
-    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum Error {
-        UndefinedError,
-        DomainNotRegistered,
-        InvalidCaller,
-        DomainNotExpired,
-        RenewTimeExpired,
-        InvalidContentKey,
-    }
+    pub type Error = ResolverError;
     pub type Result<T> = core::result::Result<T, Error>;
 
+    /// Version of the storage layout of this code, see `migrate`.
+    pub const STORAGE_VERSION: u32 = 2;
+
     impl Record {
+        /// `manager`, normally the registrar, gets the `MANAGER` role and so becomes
+        /// the first controller.
         #[ink(constructor)]
         pub fn new(admin: AccountId, manager: AccountId, grace_period: Timestamp) -> Self {
-            Self {
+            let mut instance = Self {
                 records: Mapping::default(),
                 domain_content_text: Mapping::default(),
                 sub_domain_content_text: Mapping::default(),
                 sub_domain_manager: Mapping::default(),
-                admin,
-                manager,
+                access: AccessControl::new(admin, &[PAUSER]),
                 grace_period,
-            }
-        }
-
-        #[ink(message)]
-        pub fn set_record(
-            &mut self,
-            label: Hash,
-            domain_name: String,
-            domain_owner: AccountId,
-            duration: Timestamp,
-            secret: [u8; 32],
-            resolver: AccountId,
-            domain_expiry_time: Timestamp,
-        ) -> bool {
-            let mut _label = <Sha2x256 as HashOutput>::Type::default();
-            let domain_info =
-                self.create_domain_info(domain_name.clone(), domain_owner, domain_expiry_time);
-            ink::env::hash_encoded::<Sha2x256, _>(&domain_info, &mut _label);
-            let label_hash = Hash::from(_label);
-
-            if !self.records.contains(domain_name.clone()) && label_hash == label {
-                let record_info = self.create_record_info(
-                    domain_name.clone(),
-                    domain_owner,
-                    duration,
-                    secret,
-                    resolver,
-                    domain_expiry_time,
-                );
-                self.records.insert(domain_name.clone(), &record_info);
-                true
-            } else {
-                false
-            }
+                price_oracle: None,
+                paused: false,
+                storage_version: STORAGE_VERSION,
+            };
+            instance
+                .access
+                .grant_role(admin, MANAGER, manager)
+                .expect("the admin may grant roles");
+            instance
         }
 
         #[ink(message)]
@@ -185,31 +210,17 @@ pub mod resolver {
             content_key_index: u32,
             domain_content_text: String,
         ) -> Result<()> {
-            self.only_domain_owner(domain_name.clone());
+            self.ensure_not_paused()?;
+            self.ensure_domain_owner(domain_name.clone())?;
 
-            let mut texts: ContentText = self
-                .domain_content_text
-                .get(domain_name.clone())
-                .unwrap_or_else(|| ContentText {
-                    social: vec![String::from(""); 5],  
-                    general: vec![String::from(""); 5], 
-                    address: vec![String::from(""); 5], 
-                    website: String::new(),
-                    other: String::new(),
-                });
-            match content_key.as_str() {
-                "social" => texts.social[content_key_index as usize] = domain_content_text,
-                "general" => texts.general[content_key_index as usize] = domain_content_text,
-                "address" => texts.address[content_key_index as usize] = domain_content_text,
-                "other" => texts.other = domain_content_text,
-                _ => return Err(Error::InvalidContentKey),
-            }
-            self.domain_content_text.insert(domain_name.clone(), &texts);
-            self.env().emit_event(ContentTextInfo {
+            self.write_records(
                 domain_name,
-                content_text: texts.clone(),
-            });
-            Ok(())
+                vec![RecordEntry::Text {
+                    key: content_key,
+                    index: content_key_index,
+                    value: domain_content_text,
+                }],
+            )
         }
 
         #[ink(message)]
@@ -218,20 +229,10 @@ pub mod resolver {
             domain_name: String,
             content_hash: String,
         ) -> Result<()> {
-            self.only_domain_owner(domain_name.clone());
-
-            let mut texts = self
-                .domain_content_text
-                .get(domain_name.clone())
-                .unwrap_or_default();
-            texts.website = content_hash;
-            self.domain_content_text.insert(domain_name.clone(), &texts);
+            self.ensure_not_paused()?;
+            self.ensure_domain_owner(domain_name.clone())?;
 
-            self.env().emit_event(ContentTextInfo {
-                domain_name,
-                content_text: texts.clone(),
-            });
-            Ok(())
+            self.write_records(domain_name, vec![RecordEntry::ContentHash(content_hash)])
         }
 
         #[ink(message)]
@@ -241,9 +242,10 @@ pub mod resolver {
             new_domain_owner: AccountId,
             records_availability: bool,
         ) -> Result<()> {
-            self.only_domain_owner(domain_name.clone());
+            self.ensure_not_paused()?;
+            self.ensure_domain_owner(domain_name.clone())?;
 
-            let mut record_info: Records = self.records.get(domain_name.clone()).unwrap();
+            let mut record_info: Records = self.record(domain_name.clone()).unwrap();
             record_info.domain_owner = new_domain_owner;
             self.records.insert(domain_name.clone(), &record_info);
             if !records_availability {
@@ -259,11 +261,12 @@ pub mod resolver {
 
         #[ink(message)]
         pub fn unregister_domain(&mut self, domain_name: String) -> Result<()> {
-            self.only_manager();
+            self.ensure_not_paused()?;
+            self.ensure_controller()?;
 
             if self.env().block_timestamp()
                 <= self
-                    .read_domain_expiry_time(domain_name.clone())
+                    .read_domain_expiry_time(domain_name.clone())?
                     .add(self.grace_period)
             {
                 Err(Error::DomainNotExpired)
@@ -271,50 +274,22 @@ pub mod resolver {
                 self.records.remove(domain_name.clone());
                 self.domain_content_text.remove(domain_name.clone());
 
-                Ok(())
-            }
-        }
-
-        #[ink(message)]
-        pub fn renew_domain(&mut self, domain_name: String, new_duration: Timestamp) -> Result<()> {
-            let domain_expiry_time = self.read_domain_expiry_time(domain_name.clone());
-            self.only_domain_owner(domain_name.clone());
+                // lets the price oracle charge a premium while the name is fresh
+                if let Some(price_oracle) = self.price_oracle {
+                    if !self.record_release(price_oracle, domain_name) {
+                        return Err(Error::ReleaseReportFailed);
+                    }
+                }
 
-            if self.env().block_timestamp() < domain_expiry_time
-                && self.env().block_timestamp() > domain_expiry_time.add(self.grace_period)
-            {
-                Err(Error::RenewTimeExpired)
-            } else {
-                let mut record_info = self.records.get(domain_name.clone()).unwrap();
-                record_info.duration = record_info.duration.add(new_duration);
-                record_info.domain_expiry_time = domain_expiry_time.add(new_duration);
-                self.records.insert(domain_name.clone(), &record_info);
-
-                self.env().emit_event(RenewDomainInfo {
-                    domain_name,
-                    domain_expiry_time: record_info.domain_expiry_time,
-                    domain_duration: record_info.duration,
-                });
                 Ok(())
             }
         }
 
-        #[ink(message)]
-        pub fn register_subdomain(&mut self, parent_domain: String, sub_domain: String) -> bool {
-            self.only_domain_owner(parent_domain.clone());
-            let mut parent_domain_records = self.records.get(parent_domain.clone()).unwrap();
-            parent_domain_records.sub_domain = sub_domain.clone();
-            self.records.insert(parent_domain, &parent_domain_records);
-            self.sub_domain_manager
-                .insert(sub_domain, &Self::env().caller());
-
-            true
-        }
-
         #[ink(message)]
         pub fn unregister_subdomain(&mut self, parent_domain: String) -> Result<()> {
-            self.only_domain_owner(parent_domain.clone());
-            let mut parent_records = self.records.get(parent_domain.clone()).unwrap();
+            self.ensure_not_paused()?;
+            self.ensure_domain_owner(parent_domain.clone())?;
+            let mut parent_records = self.record(parent_domain.clone()).unwrap();
             parent_records.sub_domain = String::from("");
             self.records.insert(parent_domain.clone(), &parent_records);
             Ok(())
@@ -328,13 +303,14 @@ pub mod resolver {
             content_key_index: u32,
             sub_domain_content_text: String,
         ) -> Result<()> {
-            self.only_sub_domain_manager(sub_domain_name.clone());
+            self.ensure_not_paused()?;
+            self.ensure_sub_domain_manager(sub_domain_name.clone())?;
 
             let mut texts = self
                 .sub_domain_content_text
                 .get(sub_domain_name.clone())
                 .unwrap_or_else(|| SubDomainContentText {
-                    social: vec![String::from(""); 5],  // Initialize with 5 empty strings
+                    social: vec![String::from(""); 5], // Initialize with 5 empty strings
                     general: vec![String::from(""); 5], // Initialize with 5 empty strings
                     address: vec![String::from(""); 5], // Initialize with 5 empty strings
                     website: String::new(),
@@ -360,31 +336,67 @@ pub mod resolver {
         }
 
         #[ink(message)]
-        pub fn set_grace_period(&mut self, new_grace_period: Timestamp) {
-            self.only_admin();
+        pub fn set_grace_period(&mut self, new_grace_period: Timestamp) -> Result<()> {
+            self.ensure_role(ADMIN)?;
             self.grace_period = new_grace_period;
             self.env().emit_event(GracePeriod {
                 grace_period: new_grace_period,
             });
+            Ok(())
+        }
+
+        /// Halts record writes and content updates until `unpause`.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.ensure_role(PAUSER)?;
+            self.paused = true;
+            self.env().emit_event(Paused {
+                account: self.env().caller(),
+            });
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn change_manager(&mut self, manager: AccountId) {
-            self.only_admin();
-            self.manager = manager;
+        pub fn unpause(&mut self) -> Result<()> {
+            self.ensure_role(PAUSER)?;
+            self.paused = false;
+            self.env().emit_event(Unpaused {
+                account: self.env().caller(),
+            });
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn change_sub_domain_manager(&mut self, parent_domain: String, manager: AccountId) {
-            self.only_domain_owner(parent_domain.clone());
-            let sub_domain = self.records.get(parent_domain.clone()).unwrap().sub_domain;
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
 
-            self.sub_domain_manager.insert(sub_domain, &manager);
+        /// Controllers are the accounts with the `MANAGER` role, see `grant_role`.
+        #[ink(message)]
+        pub fn is_controller(&self, account: AccountId) -> bool {
+            self.access.has_role(MANAGER, account)
         }
 
+        /// Sets the price oracle released domains are reported to, `None` to stop reporting.
         #[ink(message)]
-        pub fn read_grace_period(&self) -> Timestamp {
-            self.grace_period
+        pub fn set_price_oracle(&mut self, price_oracle: Option<AccountId>) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.price_oracle = price_oracle;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn change_sub_domain_manager(
+            &mut self,
+            parent_domain: String,
+            manager: AccountId,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_domain_owner(parent_domain.clone())?;
+            let sub_domain = self.record(parent_domain.clone()).unwrap().sub_domain;
+
+            self.sub_domain_manager.insert(sub_domain, &manager);
+            Ok(())
         }
 
         #[ink(message)]
@@ -398,52 +410,164 @@ pub mod resolver {
         }
 
         #[ink(message)]
-        pub fn read_content_hash(&self, domain_name: String) -> String {
-            self.domain_content_text.get(domain_name).unwrap().website
+        pub fn read_record(&self, domain_name: String) -> Records {
+            self.record(domain_name).unwrap()
         }
 
         #[ink(message)]
-        pub fn read_record(&self, domain_name: String) -> Records {
-            self.records.get(domain_name).unwrap()
+        pub fn read_sub_domain_owner(&self, parent_domain: String) -> AccountId {
+            let owner_record = self.record(parent_domain).unwrap();
+            owner_record.domain_owner
         }
 
         #[ink(message)]
-        pub fn read_domain_owner(&self, domain_name: String) -> AccountId {
-            let owner_record = self.records.get(domain_name).unwrap();
-            owner_record.domain_owner
+        pub fn read_sub_domain_manager(&self, sub_domain: String) -> AccountId {
+            self.sub_domain_manager.get(sub_domain.clone()).unwrap()
         }
 
         #[ink(message)]
-        pub fn read_sub_domain_owner(&self, parent_domain: String) -> AccountId {
-            let owner_record = self.records.get(parent_domain).unwrap();
-            owner_record.domain_owner
+        pub fn read_admin(&self) -> AccountId {
+            self.access.admin()
         }
 
+        /// Replaces the code of the contract, keeping records and content. Call `migrate` afterwards.
         #[ink(message)]
-        pub fn read_sub_domain_manager(&self, sub_domain: String) -> AccountId {
-            self.sub_domain_manager.get(sub_domain.clone()).unwrap()
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<()> {
+            self.ensure_role(ADMIN)?;
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+            self.env().emit_event(Upgraded { code_hash });
+            Ok(())
         }
 
+        /// Converts storage written by older code to the layout of this one after
+        /// `upgrade`. Records cannot be listed on chain, so the names of those to
+        /// convert are passed in, over as many calls as it takes; until then they
+        /// are read in their old layout. Returns the number of converted records.
         #[ink(message)]
-        pub fn read_domain_expiry_time(&self, domain_name: String) -> Timestamp {
-            let owner_record = self.records.get(domain_name).unwrap();
-            owner_record.domain_expiry_time
+        pub fn migrate(&mut self, domain_names: Vec<String>) -> Result<u32> {
+            self.ensure_role(ADMIN)?;
+            let from_version = self.storage_version;
+
+            // version 2 added `Records::registered_at`
+            let mut converted: u32 = 0;
+            for domain_name in domain_names {
+                let key = (&self.records.key(), &domain_name);
+                if ink::env::get_contract_storage::<_, Records>(&key).is_ok() {
+                    continue;
+                }
+                if let Ok(Some(record)) = ink::env::get_contract_storage::<_, RecordsV1>(&key) {
+                    self.records.insert(domain_name, &Records::from(record));
+                    converted = converted.saturating_add(1);
+                }
+            }
+
+            if from_version < STORAGE_VERSION {
+                self.storage_version = STORAGE_VERSION;
+                self.env().emit_event(Migrated {
+                    from_version,
+                    to_version: STORAGE_VERSION,
+                });
+            } else if converted == 0 {
+                return Err(Error::AlreadyMigrated);
+            }
+            Ok(converted)
         }
 
+        /// The storage layout version the storage was last migrated to.
         #[ink(message)]
-        pub fn check_domain_availablility(&self, domain_name: String) -> bool {
-            let availability = self.records.contains(domain_name);
-            !availability
+        pub fn storage_version(&self) -> u32 {
+            self.storage_version
         }
 
         #[ink(message)]
-        pub fn read_admin(&self) -> AccountId {
-            self.admin
+        pub fn read_pending_admin(&self) -> Option<AccountId> {
+            self.access.pending_admin()
+        }
+
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+            self.access.has_role(role, account)
+        }
+
+        /// Grants `role` to `account`. Only the admin may call this.
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.grant_role(sender, role, account)?;
+            self.env().emit_event(RoleGranted {
+                account,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Revokes `role` from `account`. Only the admin may call this.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.revoke_role(sender, role, account)?;
+            self.env().emit_event(RoleRevoked {
+                account,
+                role,
+                sender,
+            });
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn read_manager(&self) -> AccountId {
-            self.manager
+        pub fn renounce_role(&mut self, role: Role) -> Result<()> {
+            let sender = self.env().caller();
+            self.access.renounce_role(sender, role)?;
+            self.env().emit_event(RoleRevoked {
+                account: sender,
+                role,
+                sender,
+            });
+            Ok(())
+        }
+
+        /// Proposes the next admin, who takes over once it calls `accept_admin`.
+        #[ink(message)]
+        pub fn propose_admin(&mut self, pending_admin: AccountId) -> Result<()> {
+            self.access
+                .propose_admin(self.env().caller(), pending_admin)?;
+            self.env().emit_event(AdminTransferProposed {
+                admin: self.access.admin(),
+                pending_admin,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn accept_admin(&mut self) -> Result<()> {
+            let admin = self.env().caller();
+            let previous_admin = self.access.accept_admin(admin)?;
+            self.env().emit_event(AdminTransferred {
+                previous_admin,
+                admin,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn read_price_oracle(&self) -> Option<AccountId> {
+            self.price_oracle
+        }
+
+        fn record_release(&self, price_oracle: AccountId, domain_name: String) -> bool {
+            let mut price_oracle: contract_ref!(VnsPriceOracle, MyEnvironment) =
+                FromAccountId::from_account_id(price_oracle);
+            // the oracle needs to have granted this contract the `MANAGER` role
+            let reported = call::invoke(
+                price_oracle
+                    .call_mut()
+                    .record_release(domain_name, self.env().block_timestamp()),
+                CallLimits::default(),
+            );
+            matches!(reported, Ok(Ok(())))
         }
 
         fn create_record_info(
@@ -463,6 +587,7 @@ pub mod resolver {
                 resolver,
                 domain_expiry_time,
                 sub_domain: String::from(""),
+                registered_at: self.env().block_number(),
             }
         }
 
@@ -479,31 +604,605 @@ pub mod resolver {
             }
         }
 
-        fn only_admin(&self) {
-            let caller = Self::env().caller();
-            assert_eq!(caller, self.admin, "must be contract owner");
+        fn ensure_role(&self, role: Role) -> Result<()> {
+            Ok(self.access.ensure_role(role, self.env().caller())?)
+        }
+
+        fn ensure_not_paused(&self) -> Result<()> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
+        fn write_records(&mut self, domain_name: String, records: Vec<RecordEntry>) -> Result<()> {
+            let mut texts = self
+                .domain_content_text
+                .get(domain_name.clone())
+                .unwrap_or_else(|| ContentText {
+                    social: vec![String::new(); 5],
+                    general: vec![String::new(); 5],
+                    address: vec![String::new(); 5],
+                    website: String::new(),
+                    other: String::new(),
+                });
+            for record in records {
+                apply_record(&mut texts, record)?;
+            }
+
+            self.domain_content_text.insert(domain_name.clone(), &texts);
+            self.env().emit_event(ContentTextInfo {
+                domain_name,
+                content_text: texts,
+            });
+            Ok(())
+        }
+
+        fn ensure_controller(&self) -> Result<()> {
+            if !self.is_controller(Self::env().caller()) {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Reads the record of a domain, in the version 1 layout if `migrate` has
+        /// not converted it yet.
+        fn record(&self, domain_name: String) -> Option<Records> {
+            let key = (&self.records.key(), &domain_name);
+            match ink::env::get_contract_storage::<_, Records>(&key) {
+                Ok(record) => record,
+                Err(_) => ink::env::get_contract_storage::<_, RecordsV1>(&key)
+                    .ok()
+                    .flatten()
+                    .map(Records::from),
+            }
+        }
+
+        fn ensure_domain_owner(&self, domain_name: String) -> Result<()> {
+            let record = self.record(domain_name).ok_or(Error::DomainNotRegistered)?;
+            if record.domain_owner != Self::env().caller() {
+                return Err(Error::InvalidCaller);
+            }
+            Ok(())
+        }
+
+        fn ensure_sub_domain_manager(&self, sub_domain: String) -> Result<()> {
+            if self.sub_domain_manager.get(sub_domain) != Some(Self::env().caller()) {
+                return Err(Error::InvalidCaller);
+            }
+            Ok(())
+        }
+    }
+
+    impl VnsResolver for Record {
+        /// Writes the record of a new domain. Only controllers may call this.
+        #[ink(message)]
+        fn set_record(
+            &mut self,
+            label: Hash,
+            domain_name: String,
+            domain_owner: AccountId,
+            duration: Timestamp,
+            secret: [u8; 32],
+            resolver: AccountId,
+            domain_expiry_time: Timestamp,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_controller()?;
+
+            let mut _label = <Sha2x256 as HashOutput>::Type::default();
+            let domain_info =
+                self.create_domain_info(domain_name.clone(), domain_owner, domain_expiry_time);
+            ink::env::hash_encoded::<Sha2x256, _>(&domain_info, &mut _label);
+            let label_hash = Hash::from(_label);
+
+            // only names normalized by the registrar are accepted
+            if !name::is_normalized(&domain_name) {
+                return Err(Error::InvalidDomainName);
+            }
+            if label_hash != label {
+                return Err(Error::InvalidLabel);
+            }
+            if self.records.contains(domain_name.clone()) {
+                return Err(Error::DomainAlreadyRegistered);
+            }
+
+            let record_info = self.create_record_info(
+                domain_name.clone(),
+                domain_owner,
+                duration,
+                secret,
+                resolver,
+                domain_expiry_time,
+            );
+            self.records.insert(domain_name.clone(), &record_info);
+            Ok(())
+        }
+
+        /// Writes several records at once, all or none of them. Controllers may do
+        /// this on the owner's behalf right after registration, i.e. in the block the
+        /// domain was registered in.
+        #[ink(message)]
+        fn set_records(&mut self, domain_name: String, records: Vec<RecordEntry>) -> Result<()> {
+            self.ensure_not_paused()?;
+            if self.is_controller(self.env().caller()) {
+                let record = self
+                    .record(domain_name.clone())
+                    .ok_or(Error::DomainNotRegistered)?;
+                if record.registered_at != self.env().block_number() {
+                    return Err(Error::Unauthorized);
+                }
+            } else {
+                self.ensure_domain_owner(domain_name.clone())?;
+            }
+
+            self.write_records(domain_name, records)
+        }
+
+        /// Extends the expiry of a domain. Only controllers may call this so every
+        /// renewal goes through, and is paid for at, the registrar.
+        #[ink(message)]
+        fn renew_domain(&mut self, domain_name: String, new_duration: Timestamp) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_controller()?;
+            let domain_expiry_time = self.read_domain_expiry_time(domain_name.clone())?;
+
+            if self.env().block_timestamp() > domain_expiry_time.add(self.grace_period) {
+                Err(Error::RenewTimeExpired)
+            } else {
+                let mut record_info = self.record(domain_name.clone()).unwrap();
+                record_info.duration = record_info.duration.add(new_duration);
+                record_info.domain_expiry_time = domain_expiry_time.add(new_duration);
+                self.records.insert(domain_name.clone(), &record_info);
+
+                self.env().emit_event(RenewDomainInfo {
+                    domain_name,
+                    domain_expiry_time: record_info.domain_expiry_time,
+                    domain_duration: record_info.duration,
+                });
+                Ok(())
+            }
+        }
+
+        #[ink(message)]
+        fn register_subdomain(&mut self, parent_domain: String, sub_domain: String) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_domain_owner(parent_domain.clone())?;
+            let mut parent_domain_records = self.record(parent_domain.clone()).unwrap();
+            parent_domain_records.sub_domain = sub_domain.clone();
+            self.records.insert(parent_domain, &parent_domain_records);
+            self.sub_domain_manager
+                .insert(sub_domain, &Self::env().caller());
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn read_grace_period(&self) -> Timestamp {
+            self.grace_period
+        }
+
+        #[ink(message)]
+        fn read_content_hash(&self, domain_name: String) -> Result<String> {
+            if !self.records.contains(domain_name.clone()) {
+                return Err(Error::DomainNotRegistered);
+            }
+            Ok(self
+                .domain_content_text
+                .get(domain_name)
+                .unwrap_or_default()
+                .website)
+        }
+
+        #[ink(message)]
+        fn read_domain_owner(&self, domain_name: String) -> Result<AccountId> {
+            self.record(domain_name)
+                .map(|record| record.domain_owner)
+                .ok_or(Error::DomainNotRegistered)
+        }
+
+        #[ink(message)]
+        fn read_domain_expiry_time(&self, domain_name: String) -> Result<Timestamp> {
+            self.record(domain_name)
+                .map(|record| record.domain_expiry_time)
+                .ok_or(Error::DomainNotRegistered)
+        }
+
+        #[ink(message)]
+        fn check_domain_availablility(&self, domain_name: String) -> bool {
+            let availability = self.records.contains(domain_name);
+            !availability
+        }
+    }
+
+    fn apply_record(texts: &mut ContentText, record: RecordEntry) -> Result<()> {
+        let (slots, index, value) = match record {
+            RecordEntry::ContentHash(content_hash) => {
+                texts.website = content_hash;
+                return Ok(());
+            }
+            RecordEntry::Address { index, address } => (&mut texts.address, index, address),
+            RecordEntry::Text { key, index, value } => match key.as_str() {
+                "social" => (&mut texts.social, index, value),
+                "general" => (&mut texts.general, index, value),
+                "address" => (&mut texts.address, index, value),
+                "other" => {
+                    texts.other = value;
+                    return Ok(());
+                }
+                _ => return Err(Error::InvalidContentKey),
+            },
+        };
+        let slot = slots
+            .get_mut(index as usize)
+            .ok_or(Error::InvalidContentIndex)?;
+        *slot = value;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use vns_common::access::AccessError;
+        use vns_common::testing::set_caller;
+
+        fn new_resolver() -> Record {
+            Record::new(AccountId::from([0x1; 20]), AccountId::from([0x1; 20]), 100)
+        }
+
+        fn set_test_record(resolver: &mut Record, domain_name: &str) -> Result<()> {
+            let domain_owner = AccountId::from([0x2; 20]);
+            let domain_info =
+                resolver.create_domain_info(String::from(domain_name), domain_owner, 1000);
+            let mut label = <Sha2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Sha2x256, _>(&domain_info, &mut label);
+            resolver.set_record(
+                Hash::from(label),
+                String::from(domain_name),
+                domain_owner,
+                1000,
+                [0x0; 32],
+                AccountId::from([0x1; 20]),
+                1000,
+            )
+        }
+
+        #[ink::test]
+        fn only_controllers_set_records() {
+            let mut resolver = new_resolver();
+            assert_eq!(set_test_record(&mut resolver, "alice.vne"), Ok(()));
+            assert_eq!(
+                resolver.read_domain_owner(String::from("alice.vne")),
+                Ok(AccountId::from([0x2; 20]))
+            );
+
+            set_caller(AccountId::from([0x9; 20]));
+            assert_eq!(
+                set_test_record(&mut resolver, "bob.vne"),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn reads_of_unregistered_domains_fail() {
+            let mut resolver = new_resolver();
+            let bob = String::from("bob.vne");
+            assert_eq!(
+                resolver.read_domain_owner(bob.clone()),
+                Err(Error::DomainNotRegistered)
+            );
+            assert_eq!(
+                resolver.read_domain_expiry_time(bob.clone()),
+                Err(Error::DomainNotRegistered)
+            );
+            assert_eq!(
+                resolver.read_content_hash(bob.clone()),
+                Err(Error::DomainNotRegistered)
+            );
+            assert_eq!(
+                resolver.renew_domain(bob, 500),
+                Err(Error::DomainNotRegistered)
+            );
+
+            // a registered domain without content reads as empty
+            set_test_record(&mut resolver, "alice.vne").unwrap();
+            assert_eq!(
+                resolver.read_content_hash(String::from("alice.vne")),
+                Ok(String::new())
+            );
+        }
+
+        #[ink::test]
+        fn only_controllers_renew_domains() {
+            let mut resolver = new_resolver();
+            set_test_record(&mut resolver, "alice.vne").unwrap();
+
+            // not even the owner may renew without paying at the registrar
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                resolver.renew_domain(String::from("alice.vne"), 500),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                resolver.read_domain_expiry_time(String::from("alice.vne")),
+                Ok(1000)
+            );
+
+            set_caller(AccountId::from([0x1; 20]));
+            assert_eq!(
+                resolver.renew_domain(String::from("alice.vne"), 500),
+                Ok(())
+            );
+            assert_eq!(
+                resolver.read_domain_expiry_time(String::from("alice.vne")),
+                Ok(1500)
+            );
+
+            ink::env::test::set_block_timestamp::<MyEnvironment>(1601);
+            assert_eq!(
+                resolver.renew_domain(String::from("alice.vne"), 500),
+                Err(Error::RenewTimeExpired)
+            );
+        }
+
+        #[ink::test]
+        fn pause_blocks_owner_writes() {
+            let mut resolver = new_resolver();
+            set_test_record(&mut resolver, "alice.vne").unwrap();
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                resolver
+                    .register_subdomain(String::from("alice.vne"), String::from("pay.alice.vne")),
+                Ok(())
+            );
+
+            set_caller(AccountId::from([0x1; 20]));
+            assert_eq!(resolver.pause(), Ok(()));
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                resolver.change_sub_domain_manager(
+                    String::from("alice.vne"),
+                    AccountId::from([0x3; 20])
+                ),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                resolver.set_content_hash(String::from("alice.vne"), String::from("ipfs://alice")),
+                Err(Error::Paused)
+            );
+        }
+
+        #[ink::test]
+        fn upgrade_and_migrate_require_admin() {
+            let mut resolver = new_resolver();
+            set_caller(AccountId::from([0x9; 20]));
+            let missing_admin = Error::Access(AccessError::MissingRole { role: ADMIN });
+            assert_eq!(resolver.upgrade([0x5; 32]), Err(missing_admin));
+            assert_eq!(resolver.migrate(vec![]), Err(missing_admin));
         }
 
-        fn only_manager(&self) {
-            let caller = Self::env().caller();
-            assert_eq!(caller, self.manager, " must be contract manager");
+        #[ink::test]
+        fn migrate_converts_version_1_records() {
+            let mut resolver = new_resolver();
+            assert_eq!(resolver.storage_version(), STORAGE_VERSION);
+            assert_eq!(resolver.migrate(vec![]), Err(Error::AlreadyMigrated));
+
+            set_test_record(&mut resolver, "bob.vne").unwrap();
+            // as left behind by version 1 code
+            resolver.storage_version = 1;
+            let alice = String::from("alice.vne");
+            let key = (&resolver.records.key(), &alice);
+            ink::env::set_contract_storage(
+                &key,
+                &RecordsV1 {
+                    domain_name: alice.clone(),
+                    domain_owner: AccountId::from([0x2; 20]),
+                    duration: 100,
+                    secret: [0x7; 32],
+                    resolver: AccountId::from([0x3; 20]),
+                    domain_expiry_time: 200,
+                    sub_domain: String::from(""),
+                },
+            );
+            assert!(ink::env::get_contract_storage::<_, Records>(&key).is_err());
+
+            // read in the old layout until converted
+            assert_eq!(
+                resolver.read_domain_owner(alice.clone()),
+                Ok(AccountId::from([0x2; 20]))
+            );
+            set_caller(AccountId::from([0x2; 20]));
+            resolver
+                .set_content_hash(alice.clone(), String::from("ipfs://alice"))
+                .unwrap();
+
+            set_caller(AccountId::from([0x1; 20]));
+            assert_eq!(
+                resolver.migrate(vec![alice.clone(), String::from("bob.vne")]),
+                Ok(1)
+            );
+            assert_eq!(resolver.storage_version(), STORAGE_VERSION);
+            let record = ink::env::get_contract_storage::<_, Records>(&key)
+                .unwrap()
+                .unwrap();
+            assert_eq!(record.domain_owner, AccountId::from([0x2; 20]));
+            assert_eq!(record.domain_expiry_time, 200);
+            assert_eq!(record.registered_at, 0);
+            assert_eq!(
+                resolver.read_content_hash(alice.clone()),
+                Ok(String::from("ipfs://alice"))
+            );
+            assert_eq!(resolver.migrate(vec![alice]), Err(Error::AlreadyMigrated));
         }
 
-        fn only_domain_owner(&self, domain_name: String) {
-            let caller = Self::env().caller();
+        #[ink::test]
+        fn controllers_set_records_of_new_domains_only() {
+            let mut resolver = new_resolver();
+            set_test_record(&mut resolver, "alice.vne").unwrap();
+            set_test_record(&mut resolver, "bob.vne").unwrap();
+            let alice = String::from("alice.vne");
+            let bob = String::from("bob.vne");
+            let content_hash = |hash: &str| vec![RecordEntry::ContentHash(String::from(hash))];
+
+            // in the block of the registration
+            assert_eq!(
+                resolver.set_records(alice.clone(), content_hash("ipfs://a")),
+                Ok(())
+            );
+            assert_eq!(
+                resolver.set_records(alice.clone(), content_hash("ipfs://b")),
+                Ok(())
+            );
+
+            ink::env::test::advance_block::<MyEnvironment>();
+            // the owner's records are theirs to change from then on
+            assert_eq!(
+                resolver.set_records(alice.clone(), content_hash("ipfs://c")),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                resolver.read_content_hash(alice.clone()),
+                Ok(String::from("ipfs://b"))
+            );
+            set_caller(AccountId::from([0x2; 20]));
+            assert_eq!(
+                resolver.set_records(alice, content_hash("ipfs://c")),
+                Ok(())
+            );
+
+            // nor does a domain without records yet get them from a controller later
+            set_caller(AccountId::from([0x1; 20]));
             assert_eq!(
-                caller,
-                self.read_domain_owner(domain_name),
-                "must be domain owner!"
+                resolver.set_records(bob.clone(), content_hash("ipfs://d")),
+                Err(Error::Unauthorized)
             );
+            assert_eq!(resolver.read_content_hash(bob), Ok(String::new()));
         }
-        fn only_sub_domain_manager(&self, sub_domain: String) {
-            let caller = Self::env().caller();
+
+        #[ink::test]
+        fn set_records_writes_all_or_nothing() {
+            let mut resolver = new_resolver();
+            set_test_record(&mut resolver, "alice.vne").unwrap();
+            let alice = String::from("alice.vne");
+
+            // the controller writes on behalf of the owner
+            assert_eq!(
+                resolver.set_records(
+                    alice.clone(),
+                    vec![
+                        RecordEntry::ContentHash(String::from("ipfs://alice")),
+                        RecordEntry::Address {
+                            index: 0,
+                            address: String::from("0xa11ce"),
+                        },
+                        RecordEntry::Text {
+                            key: String::from("other"),
+                            index: 0,
+                            value: String::from("hello"),
+                        },
+                    ],
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                resolver.read_content_hash(alice.clone()),
+                Ok(String::from("ipfs://alice"))
+            );
+            let texts = resolver.read_domain_content_text(alice.clone());
+            assert_eq!(texts.address[0], "0xa11ce");
+            assert_eq!(texts.other, "hello");
+
+            let invalid = vec![
+                RecordEntry::ContentHash(String::from("ipfs://other")),
+                RecordEntry::Address {
+                    index: 5,
+                    address: String::from("0xa11ce"),
+                },
+            ];
+            assert_eq!(
+                resolver.set_records(alice.clone(), invalid),
+                Err(Error::InvalidContentIndex)
+            );
             assert_eq!(
-                caller,
-                self.read_sub_domain_manager(sub_domain),
-                "must be sub domain manager!"
+                resolver.set_records(String::from("bob.vne"), vec![]),
+                Err(Error::DomainNotRegistered)
             );
+
+            set_caller(AccountId::from([0x9; 20]));
+            assert_eq!(
+                resolver.set_records(alice, vec![]),
+                Err(Error::InvalidCaller)
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::ContractsBackend;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test(environment = crate::resolver::MyEnvironment)]
+        async fn upgrade_keeps_records<Client: E2EBackend<MyEnvironment>>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut alice = [0u8; 20];
+            alice.copy_from_slice(&ink_e2e::alice().public_key().0[..20]);
+            let alice = AccountId::from(alice);
+            let mut constructor = RecordRef::new(alice, alice, 1000);
+            let resolver = client
+                .instantiate("resolver", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let mut call_builder = resolver.call_builder::<Record>();
+            let domain_info = Domain {
+                domain_name: String::from("alice.vne"),
+                domain_owner: alice,
+                domain_expiry_time: u64::MAX,
+            };
+            let mut label = <Sha2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Sha2x256, _>(&domain_info, &mut label);
+            let set_record = call_builder.set_record(
+                Hash::from(label),
+                String::from("alice.vne"),
+                alice,
+                1000,
+                [0x0; 32],
+                alice,
+                u64::MAX,
+            );
+            client
+                .call(&ink_e2e::alice(), &set_record)
+                .submit()
+                .await
+                .expect("set record failed");
+
+            // the code of this version stands in for the next one
+            let code_hash = client
+                .upload("resolver", &ink_e2e::alice())
+                .submit()
+                .await
+                .expect("upload failed")
+                .code_hash;
+            let result = client
+                .call(&ink_e2e::alice(), &call_builder.upgrade(code_hash))
+                .submit()
+                .await
+                .expect("upgrade failed");
+            assert_eq!(result.return_value(), Ok(()));
+
+            let read_owner = call_builder.read_domain_owner(String::from("alice.vne"));
+            let result = client
+                .call(&ink_e2e::alice(), &read_owner)
+                .dry_run()
+                .await?;
+            assert_eq!(result.return_value(), Ok(alice));
+            let migrate = call_builder.migrate(vec![String::from("alice.vne")]);
+            let result = client.call(&ink_e2e::alice(), &migrate).dry_run().await?;
+            assert_eq!(result.return_value(), Err(Error::AlreadyMigrated));
+            Ok(())
         }
     }
 }
@@ -45,18 +45,19 @@ async function main() {
         }
     }
 
-    async function read_manager() {
-        const { result, gasUsed, output } = await contract.query["readManager"](
+    async function is_controller(account) {
+        const { result, gasUsed, output } = await contract.query["isController"](
             userKeyring.address,
             { gasLimit: gasLimit, storageDepositLimit: null },
+            account,
         );
 
         if (result.isOk) {
-            const Manager = output.toHuman()
-            console.log("Manager address is : ".yellow, Manager.Ok);
-            return Manager;
+            const isController = output.toHuman()
+            console.log("Is controller : ".yellow, isController.Ok);
+            return isController;
         } else {
-            console.error('Failed to read Manager address:', output);
+            console.error('Failed to read controller status:', output);
             return null;
         }
     }
@@ -78,7 +79,7 @@ async function main() {
     }
 
     async function read_grace_period() {
-        const { result, gasUsed, output } = await contract.query["readGracePeriod"](
+        const { result, gasUsed, output } = await contract.query["vnsResolver::readGracePeriod"](
             userKeyring.address,
             { gasLimit: gasLimit, storageDepositLimit: null },
         );
@@ -108,7 +109,7 @@ async function main() {
         }
     }
     async function read_content_hash(domain_name) {
-        const { result, gasUsed, output } = await contract.query["readContentHash"](
+        const { result, gasUsed, output } = await contract.query["vnsResolver::readContentHash"](
             userKeyring.address,
             { gasLimit: gasLimit, storageDepositLimit: null }, domain_name
         );
@@ -159,7 +160,7 @@ async function main() {
 
 
     async function read_domain_owner(domain_name) {
-        const { result, gasUsed, output } = await contract.query["readDomainOwner"](
+        const { result, gasUsed, output } = await contract.query["vnsResolver::readDomainOwner"](
             userKeyring.address,
             { gasLimit: gasLimit, storageDepositLimit: null }, domain_name
         );
@@ -175,7 +176,7 @@ async function main() {
     }
 
     async function read_domain_expiry_time(domain_name) {
-        const { result, gasUsed, output } = await contract.query["readDomainExpiryTime"](
+        const { result, gasUsed, output } = await contract.query["vnsResolver::readDomainExpiryTime"](
             userKeyring.address,
             { gasLimit: gasLimit, storageDepositLimit: null }, domain_name
         );
@@ -191,7 +192,7 @@ async function main() {
     }
 
     async function check_domain_availablility(domain_name) {
-        const { result, gasUsed, output } = await contract.query["checkDomainAvailablility"](
+        const { result, gasUsed, output } = await contract.query["vnsResolver::checkDomainAvailablility"](
             userKeyring.address,
             { gasLimit: gasLimit, storageDepositLimit: null }, domain_name
         );
@@ -254,9 +255,10 @@ async function main() {
             });
     }
 
-    async function change_manager(manager) {
+    // role 2 is MANAGER, its members are the controllers allowed to write records
+    async function grant_role(role, account) {
         await contract.tx
-            .changeManager({ storageDepositLimit, gasLimit }, manager)
+            .grantRole({ storageDepositLimit, gasLimit }, role, account)
             .signAndSend(userKeyring, result => {
                 if (result.status.isInBlock) {
                     console.log(`initialised in block : ${result.status.asInBlock}`.cyan);
@@ -294,12 +296,12 @@ async function main() {
     // await unregister_domain("akh.vne");
     // await set_content_hash("arpitssk.vne","website");
     // await set_domain_content_text("arpitssk.vne", "general",2,"website");
-    // await change_manager("0x1bacaecc83ed515b77a8d39f24e46e05c8bbc920");
+    // await grant_role(2, "0x1bacaecc83ed515b77a8d39f24e46e05c8bbc920");
     // await register_subdomain("akz.vne", "arpit.akz.vne");
     // await set_grace_period(100);
 
     // await read_owner();
-    // await read_manager();
+    // await is_controller("<registrar address>");
     // await read_grace_period();
     
     await read_domain_record("arpitssk.vne");
@@ -0,0 +1,245 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// A minimal PSP22 token to test payments in tokens against. Anyone may mint.
+#[ink::contract(env = MyEnvironment)]
+pub mod psp22_mock {
+    pub type MyEnvironment = vns_common::VnsEnvironment;
+
+    use ink::prelude::{string::String, vec::Vec};
+    use ink::storage::Mapping;
+
+    pub use vns_common::token::PSP22Error;
+
+    /// The PSP22 messages the registrar calls, with the standard selectors.
+    #[ink::trait_definition]
+    pub trait PSP22 {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance;
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance;
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error>;
+    }
+
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct Psp22Mock {
+        total_supply: Balance,
+        balances: Mapping<AccountId, Balance>,
+        allowances: Mapping<(AccountId, AccountId), Balance>,
+        fail_transfers: bool,
+        trap_transfers: bool,
+    }
+
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        amount: Balance,
+    }
+
+    impl Psp22Mock {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) {
+            self.total_supply = self.total_supply.saturating_add(value);
+            let balance = self.balance_of(to).saturating_add(value);
+            self.balances.insert(to, &balance);
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(to),
+                value,
+            });
+        }
+
+        /// Makes every transfer fail, to test how callers handle it.
+        #[ink(message)]
+        pub fn set_fail_transfers(&mut self, fail_transfers: bool) {
+            self.fail_transfers = fail_transfers;
+        }
+
+        /// Makes every transfer trap rather than fail with an error.
+        #[ink(message)]
+        pub fn set_trap_transfers(&mut self, trap_transfers: bool) {
+            self.trap_transfers = trap_transfers;
+        }
+
+        fn move_balance(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<(), PSP22Error> {
+            if self.trap_transfers {
+                panic!("transfers trap");
+            }
+            if self.fail_transfers {
+                return Err(PSP22Error::Custom(String::from("transfers disabled")));
+            }
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+            self.balances.insert(from, &(from_balance - value));
+            let to_balance = self.balance_of(to).saturating_add(value);
+            self.balances.insert(to, &to_balance);
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(to),
+                value,
+            });
+            Ok(())
+        }
+    }
+
+    impl PSP22 for Psp22Mock {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.total_supply
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.move_balance(self.env().caller(), to, value)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let spender = self.env().caller();
+            let allowance = self.allowance(from, spender);
+            if allowance < value {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+            self.move_balance(from, to, value)?;
+            self.allowances
+                .insert((from, spender), &(allowance - value));
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error> {
+            let owner = self.env().caller();
+            self.allowances.insert((owner, spender), &value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                amount: value,
+            });
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use vns_common::testing::set_caller;
+
+        const ALICE: [u8; 20] = [0x1; 20];
+        const REGISTRAR: [u8; 20] = [0x2; 20];
+
+        #[ink::test]
+        fn transfer_from_needs_allowance() {
+            let mut token = Psp22Mock::new();
+            token.mint(ALICE, 1000);
+            assert_eq!(
+                token.transfer_from(ALICE, REGISTRAR, 300, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+
+            // alice approves the registrar, which then collects the charge
+            set_caller(ALICE);
+            token.approve(REGISTRAR, 500).unwrap();
+            set_caller(REGISTRAR);
+            assert_eq!(
+                token.transfer_from(ALICE, REGISTRAR, 300, Vec::new()),
+                Ok(())
+            );
+
+            assert_eq!(token.balance_of(ALICE), 700);
+            assert_eq!(token.balance_of(REGISTRAR), 300);
+            assert_eq!(token.allowance(ALICE, REGISTRAR), 200);
+            assert_eq!(
+                token.transfer_from(ALICE, REGISTRAR, 300, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+        }
+
+        #[ink::test]
+        fn failing_transfers_keep_balances() {
+            let mut token = Psp22Mock::new();
+            token.mint(ALICE, 1000);
+            set_caller(ALICE);
+            token.approve(REGISTRAR, 1000).unwrap();
+            assert_eq!(
+                token.transfer(REGISTRAR, 2000, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+
+            token.set_fail_transfers(true);
+            set_caller(REGISTRAR);
+            assert!(token
+                .transfer_from(ALICE, REGISTRAR, 100, Vec::new())
+                .is_err());
+            assert_eq!(token.balance_of(ALICE), 1000);
+            assert_eq!(token.allowance(ALICE, REGISTRAR), 1000);
+        }
+    }
+}
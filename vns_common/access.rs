@@ -0,0 +1,265 @@
+//! Role based access control shared by the VNS contracts.
+//!
+//! Every contract has a single admin, who grants and revokes the other roles.
+//! The admin role itself only changes hands in two steps: the admin proposes a
+//! successor, who then has to accept. A wrong address therefore cannot lock
+//! the contract.
+
+use ink::storage::Mapping;
+
+/// The account id of the VNS contracts' environment.
+pub type AccountId = [u8; 20];
+/// A role that can be granted to accounts. Roles encode as their index, which
+/// is how they are stored, so new roles go at the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Role {
+    /// Configures the contract and grants and revokes the other roles.
+    Admin,
+    /// Sets prices in the price oracle.
+    PriceSetter,
+    /// A contract allowed to write on behalf of users, e.g. a registrar on the resolver.
+    Manager,
+    /// Pauses and unpauses the contract.
+    Pauser,
+    /// Withdraws from the registrar treasury.
+    Treasurer,
+    /// Reports the USD exchange rate to the price oracle.
+    RateReporter,
+}
+
+pub const ADMIN: Role = Role::Admin;
+pub const PRICE_SETTER: Role = Role::PriceSetter;
+pub const MANAGER: Role = Role::Manager;
+pub const PAUSER: Role = Role::Pauser;
+pub const TREASURER: Role = Role::Treasurer;
+pub const RATE_REPORTER: Role = Role::RateReporter;
+
+/// Why an access controlled call was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum AccessError {
+    /// The caller does not have `role`.
+    MissingRole { role: Role },
+    /// The admin role cannot be granted, revoked or renounced, only transferred.
+    AdminRole,
+    /// The caller is not the proposed admin, or no admin was proposed.
+    NotPendingAdmin,
+}
+
+#[ink::event]
+pub struct RoleGranted {
+    #[ink(topic)]
+    pub account: AccountId,
+    pub role: Role,
+    pub sender: AccountId,
+}
+
+#[ink::event]
+pub struct RoleRevoked {
+    #[ink(topic)]
+    pub account: AccountId,
+    pub role: Role,
+    pub sender: AccountId,
+}
+
+#[ink::event]
+pub struct AdminTransferProposed {
+    pub admin: AccountId,
+    pub pending_admin: AccountId,
+}
+
+#[ink::event]
+pub struct AdminTransferred {
+    pub previous_admin: AccountId,
+    pub admin: AccountId,
+}
+
+/// The role members of a contract, kept in its storage.
+#[ink::storage_item]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct AccessControl {
+    admin: AccountId,
+    pending_admin: Option<AccountId>,
+    members: Mapping<(Role, AccountId), ()>,
+}
+
+impl AccessControl {
+    /// Makes `admin` the admin and grants it `roles` as well.
+    pub fn new(admin: AccountId, roles: &[Role]) -> Self {
+        let mut access = Self {
+            admin,
+            pending_admin: None,
+            members: Mapping::default(),
+        };
+        for role in roles.iter().filter(|role| **role != ADMIN) {
+            access.members.insert((*role, admin), &());
+        }
+        access
+    }
+
+    pub fn admin(&self) -> AccountId {
+        self.admin
+    }
+
+    pub fn pending_admin(&self) -> Option<AccountId> {
+        self.pending_admin
+    }
+
+    pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+        if role == ADMIN {
+            return account == self.admin;
+        }
+        self.members.contains((role, account))
+    }
+
+    pub fn ensure_role(&self, role: Role, account: AccountId) -> Result<(), AccessError> {
+        if !self.has_role(role, account) {
+            return Err(AccessError::MissingRole { role });
+        }
+        Ok(())
+    }
+
+    /// Grants `role` to `account`, the caller has to be the admin.
+    pub fn grant_role(
+        &mut self,
+        caller: AccountId,
+        role: Role,
+        account: AccountId,
+    ) -> Result<(), AccessError> {
+        self.ensure_role(ADMIN, caller)?;
+        if role == ADMIN {
+            return Err(AccessError::AdminRole);
+        }
+        self.members.insert((role, account), &());
+        Ok(())
+    }
+
+    /// Revokes `role` from `account`, the caller has to be the admin.
+    pub fn revoke_role(
+        &mut self,
+        caller: AccountId,
+        role: Role,
+        account: AccountId,
+    ) -> Result<(), AccessError> {
+        self.ensure_role(ADMIN, caller)?;
+        if role == ADMIN {
+            return Err(AccessError::AdminRole);
+        }
+        self.members.remove((role, account));
+        Ok(())
+    }
+
+    /// Gives up a role of the caller.
+    pub fn renounce_role(&mut self, caller: AccountId, role: Role) -> Result<(), AccessError> {
+        if role == ADMIN {
+            return Err(AccessError::AdminRole);
+        }
+        self.ensure_role(role, caller)?;
+        self.members.remove((role, caller));
+        Ok(())
+    }
+
+    /// Proposes `pending_admin` as the next admin, replacing any earlier proposal.
+    pub fn propose_admin(
+        &mut self,
+        caller: AccountId,
+        pending_admin: AccountId,
+    ) -> Result<(), AccessError> {
+        self.ensure_role(ADMIN, caller)?;
+        self.pending_admin = Some(pending_admin);
+        Ok(())
+    }
+
+    /// Makes the proposed admin, who has to be the caller, the admin.
+    /// Returns the previous admin.
+    pub fn accept_admin(&mut self, caller: AccountId) -> Result<AccountId, AccessError> {
+        if self.pending_admin != Some(caller) {
+            return Err(AccessError::NotPendingAdmin);
+        }
+        self.pending_admin = None;
+        Ok(core::mem::replace(&mut self.admin, caller))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: AccountId = [0x1; 20];
+    const BOB: AccountId = [0x2; 20];
+
+    #[ink::test]
+    fn new_grants_roles_to_admin() {
+        let access = AccessControl::new(ALICE, &[PAUSER]);
+        assert!(access.has_role(ADMIN, ALICE));
+        assert!(access.has_role(PAUSER, ALICE));
+        assert!(!access.has_role(TREASURER, ALICE));
+        assert_eq!(
+            access.ensure_role(ADMIN, BOB),
+            Err(AccessError::MissingRole { role: ADMIN })
+        );
+    }
+
+    #[ink::test]
+    fn grant_revoke_and_renounce_work() {
+        let mut access = AccessControl::new(ALICE, &[]);
+        assert_eq!(
+            access.grant_role(BOB, MANAGER, BOB),
+            Err(AccessError::MissingRole { role: ADMIN })
+        );
+        assert_eq!(
+            access.grant_role(ALICE, ADMIN, BOB),
+            Err(AccessError::AdminRole)
+        );
+
+        assert_eq!(access.grant_role(ALICE, MANAGER, BOB), Ok(()));
+        assert!(access.has_role(MANAGER, BOB));
+        assert_eq!(access.revoke_role(ALICE, MANAGER, BOB), Ok(()));
+        assert!(!access.has_role(MANAGER, BOB));
+
+        assert_eq!(access.grant_role(ALICE, PAUSER, BOB), Ok(()));
+        assert_eq!(access.renounce_role(BOB, PAUSER), Ok(()));
+        assert_eq!(
+            access.renounce_role(BOB, PAUSER),
+            Err(AccessError::MissingRole { role: PAUSER })
+        );
+        assert_eq!(
+            access.renounce_role(ALICE, ADMIN),
+            Err(AccessError::AdminRole)
+        );
+    }
+
+    #[test]
+    fn roles_encode_as_before() {
+        // roles were stored as these `u8`s before they were an enum
+        assert_eq!(scale::Encode::encode(&ADMIN), [0]);
+        assert_eq!(scale::Encode::encode(&MANAGER), [2]);
+        assert_eq!(scale::Encode::encode(&RATE_REPORTER), [5]);
+        assert!(<Role as scale::Decode>::decode(&mut &[6][..]).is_err());
+    }
+
+    #[ink::test]
+    fn admin_transfer_takes_two_steps() {
+        let mut access = AccessControl::new(ALICE, &[]);
+        assert_eq!(access.accept_admin(BOB), Err(AccessError::NotPendingAdmin));
+        assert_eq!(
+            access.propose_admin(BOB, BOB),
+            Err(AccessError::MissingRole { role: ADMIN })
+        );
+
+        assert_eq!(access.propose_admin(ALICE, BOB), Ok(()));
+        assert_eq!(access.pending_admin(), Some(BOB));
+        // proposing does not hand anything over yet
+        assert_eq!(access.admin(), ALICE);
+        assert_eq!(
+            access.accept_admin(ALICE),
+            Err(AccessError::NotPendingAdmin)
+        );
+
+        assert_eq!(access.accept_admin(BOB), Ok(ALICE));
+        assert_eq!(access.admin(), BOB);
+        assert_eq!(access.pending_admin(), None);
+        assert!(!access.has_role(ADMIN, ALICE));
+    }
+}
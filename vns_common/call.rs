@@ -0,0 +1,158 @@
+//! Cross-contract calls that report failures instead of trapping.
+//!
+//! `invoke` runs a call built through a typed contract reference, e.g.
+//! `contract_ref!(VnsResolver, E)`, with `try_invoke` and the caller's
+//! configured limits, turning environment and language errors into a
+//! [`CallError`] the caller can map into its own error type.
+
+use ink::env::call::utils::{ReturnType, Set};
+use ink::env::call::{build_call, Call, CallBuilder, ExecutionInput};
+use ink::env::{Environment, ReturnErrorCode};
+use ink::{LangError, MessageResult};
+
+/// Resource limits of the cross-contract calls a contract makes. Zero means
+/// no limit, which is also the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, scale::Encode, scale::Decode)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct CallLimits {
+    pub ref_time_limit: u64,
+    pub proof_size_limit: u64,
+    pub storage_deposit_limit: u128,
+}
+
+#[ink::event]
+pub struct CallLimitsSet {
+    pub ref_time_limit: u64,
+    pub proof_size_limit: u64,
+    pub storage_deposit_limit: u128,
+}
+
+/// Why a cross-contract call failed before the callee could answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum CallError {
+    /// The callee trapped, e.g. it panicked or ran out of gas.
+    Trapped,
+    /// There is no contract at the callee address.
+    NotCallable,
+    /// The callee has no message with the selector, e.g. because of a typo.
+    UnknownMessage,
+    /// The callee's answer is not of the expected type.
+    Decode,
+    /// Any other error of the environment.
+    Environment,
+}
+
+/// A call built through a contract reference, ready to be invoked.
+pub type ReadyCall<E, Args, R> =
+    CallBuilder<E, Set<Call<E>>, Set<ExecutionInput<Args>>, Set<ReturnType<R>>>;
+
+/// Runs `call` within `limits` and decodes its answer as `R`.
+///
+/// With the `testing` feature the call is answered by the callee mocked with
+/// [`crate::testing::mock_callee`] instead.
+pub fn invoke<E, Args, R>(call: ReadyCall<E, Args, R>, limits: CallLimits) -> Result<R, CallError>
+where
+    E: Environment<Balance = u128>,
+    Args: scale::Encode,
+    R: scale::Decode,
+{
+    let params = call
+        .ref_time_limit(limits.ref_time_limit)
+        .proof_size_limit(limits.proof_size_limit)
+        .storage_deposit_limit(limits.storage_deposit_limit)
+        .transferred_value(0)
+        .params();
+    #[cfg(feature = "testing")]
+    let result = crate::testing::invoke(params.callee(), params.exec_input());
+    #[cfg(not(feature = "testing"))]
+    let result = params.try_invoke();
+    map_result(result)
+}
+
+/// Calls `callee` with a hand built `input`, for contracts without a trait
+/// definition, e.g. PSP22 tokens.
+pub fn invoke_selector<E, Args, R>(
+    callee: E::AccountId,
+    limits: CallLimits,
+    input: ExecutionInput<Args>,
+) -> Result<R, CallError>
+where
+    E: Environment<Balance = u128>,
+    Args: scale::Encode,
+    R: scale::Decode,
+{
+    invoke(
+        build_call::<E>()
+            .call(callee)
+            .exec_input(input)
+            .returns::<R>(),
+        limits,
+    )
+}
+
+/// Maps the result of `try_invoke` into the callee's answer or a [`CallError`].
+pub fn map_result<R>(result: ink::env::Result<MessageResult<R>>) -> Result<R, CallError> {
+    match result {
+        Ok(Ok(answer)) => Ok(answer),
+        Ok(Err(LangError::CouldNotReadInput)) => Err(CallError::UnknownMessage),
+        Ok(Err(_)) => Err(CallError::Environment),
+        Err(ink::env::Error::ReturnError(ReturnErrorCode::CalleeTrapped)) => {
+            Err(CallError::Trapped)
+        }
+        Err(ink::env::Error::ReturnError(
+            ReturnErrorCode::CodeNotFound | ReturnErrorCode::NotCallable,
+        )) => Err(CallError::NotCallable),
+        Err(ink::env::Error::Decode(_)) => Err(CallError::Decode),
+        Err(_) => Err(CallError::Environment),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_result_works() {
+        assert_eq!(map_result::<bool>(Ok(Ok(true))), Ok(true));
+        // a callee error is an answer, the caller decides what it means
+        assert_eq!(map_result::<Result<(), u8>>(Ok(Ok(Err(3)))), Ok(Err(3)));
+        assert_eq!(
+            map_result::<bool>(Ok(Err(LangError::CouldNotReadInput))),
+            Err(CallError::UnknownMessage)
+        );
+        assert_eq!(
+            map_result::<bool>(Err(ink::env::Error::ReturnError(
+                ReturnErrorCode::CalleeTrapped
+            ))),
+            Err(CallError::Trapped)
+        );
+        assert_eq!(
+            map_result::<bool>(Err(ink::env::Error::ReturnError(
+                ReturnErrorCode::CodeNotFound
+            ))),
+            Err(CallError::NotCallable)
+        );
+        assert_eq!(
+            map_result::<bool>(Err(ink::env::Error::ReturnError(
+                ReturnErrorCode::NotCallable
+            ))),
+            Err(CallError::NotCallable)
+        );
+        assert_eq!(
+            map_result::<bool>(Err(ink::env::Error::Decode(scale::Error::from(
+                "bad answer"
+            )))),
+            Err(CallError::Decode)
+        );
+        assert_eq!(
+            map_result::<bool>(Err(ink::env::Error::ReturnError(
+                ReturnErrorCode::TransferFailed
+            ))),
+            Err(CallError::Environment)
+        );
+    }
+}
@@ -0,0 +1,47 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Types and helpers shared by the VNS contracts.
+//!
+//! The contracts are upgraded in place with `set_code_hash`, so new code has
+//! to read the storage the old code left behind. Fields added to a contract's
+//! storage struct therefore go at its end and are `Lazy` or `Mapping`, which
+//! keeps the packed root layout unchanged. Any other layout change bumps the
+//! contract's storage version and converts the old values in its `migrate`.
+
+extern crate alloc;
+
+pub mod access;
+pub mod call;
+pub mod merkle;
+pub mod name;
+pub mod nft;
+pub mod price;
+pub mod record;
+pub mod resolver;
+#[cfg(feature = "std")]
+pub mod testing;
+pub mod token;
+
+pub use access::AccountId;
+
+/// The balance of the VNS contracts' environment.
+pub type Balance = u128;
+/// The hash of the VNS contracts' environment.
+pub type Hash = [u8; 32];
+/// The timestamp of the VNS contracts' environment, in milliseconds.
+pub type Timestamp = u64;
+
+/// The chain environment of the VNS contracts, which uses 20 byte accounts.
+/// Sharing it lets one contract's tests deploy and call the others.
+#[derive(Clone)]
+pub struct VnsEnvironment;
+
+impl ink::env::Environment for VnsEnvironment {
+    const MAX_EVENT_TOPICS: usize = 3;
+    type AccountId = AccountId;
+    type Balance = Balance;
+    type Hash = Hash;
+    type Timestamp = Timestamp;
+    type BlockNumber = u32;
+    type ChainExtension = ink::env::NoChainExtension;
+}
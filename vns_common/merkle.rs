@@ -0,0 +1,51 @@
+//! Merkle proofs over SHA2-256, as used for registration allowlists.
+//!
+//! Pairs are hashed in sorted order, so a proof is just the list of sibling
+//! hashes from the leaf up to the root, without left/right flags.
+
+use ink::env::hash::{HashOutput, Sha2x256};
+
+pub type Hash = [u8; 32];
+
+/// Hashes two nodes into their parent, the smaller one first.
+pub fn hash_pair(a: &Hash, b: &Hash) -> Hash {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut input = [0u8; 64];
+    input[..32].copy_from_slice(first);
+    input[32..].copy_from_slice(second);
+
+    let mut output = <Sha2x256 as HashOutput>::Type::default();
+    ink::env::hash_bytes::<Sha2x256>(&input, &mut output);
+    output
+}
+
+/// Returns `true` if `proof` proves that `leaf` is part of the tree with `root`.
+pub fn verify(root: &Hash, leaf: Hash, proof: &[Hash]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(&node, sibling))
+        == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_works() {
+        let leaves = [[0x1; 32], [0x2; 32], [0x3; 32], [0x4; 32]];
+        let left = hash_pair(&leaves[0], &leaves[1]);
+        let right = hash_pair(&leaves[2], &leaves[3]);
+        let root = hash_pair(&left, &right);
+
+        assert!(verify(&root, leaves[0], &[leaves[1], right]));
+        assert!(verify(&root, leaves[3], &[leaves[2], left]));
+        assert!(!verify(&root, leaves[0], &[leaves[2], right]));
+        assert!(!verify(&root, [0x5; 32], &[leaves[1], right]));
+    }
+
+    #[test]
+    fn single_leaf_tree_needs_no_proof() {
+        assert!(verify(&[0x1; 32], [0x1; 32], &[]));
+    }
+}
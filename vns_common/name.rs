@@ -0,0 +1,199 @@
+//! Normalization and validation of domain names.
+//!
+//! A domain name is a list of labels separated by `.`, the last one being the TLD,
+//! e.g. `alice.vne`. Names are case folded and NFC normalized before they are
+//! validated, so `Alice.VNE` and `alice.vne` are the same name.
+
+use alloc::string::String;
+use unicode_normalization::UnicodeNormalization;
+
+/// The TLD domains are registered under.
+pub const DEFAULT_TLD: &str = "vne";
+/// Maximum number of characters in a single label.
+pub const MAX_LABEL_LENGTH: usize = 63;
+/// Maximum number of characters in a name, separators included.
+pub const MAX_NAME_LENGTH: usize = 253;
+
+/// Why a name was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum InvalidNameReason {
+    /// The name is empty.
+    Empty,
+    /// The name is longer than `MAX_NAME_LENGTH`.
+    NameTooLong,
+    /// The name has an empty label, e.g. `.vne` or `a..vne`.
+    EmptyLabel,
+    /// A label is longer than `MAX_LABEL_LENGTH`.
+    LabelTooLong,
+    /// A label starts or ends with a hyphen.
+    InvalidHyphen,
+    /// A label contains something other than letters, digits and hyphens.
+    InvalidCharacter,
+    /// The name does not end with the expected TLD.
+    MissingTld,
+    /// The name has more than one label in front of the TLD. Subdomains are created
+    /// by the owner of their parent, not registered.
+    Subdomain,
+}
+
+/// Case folds and NFC normalizes `name` without validating it.
+pub fn fold(name: &str) -> String {
+    name.chars().flat_map(char::to_lowercase).nfc().collect()
+}
+
+/// Normalizes `name` and checks that it is made of valid labels, at least one of
+/// them in front of the TLD. Returns the normalized name.
+pub fn normalize(name: &str) -> Result<String, InvalidNameReason> {
+    let name = fold(name);
+    if name.is_empty() {
+        return Err(InvalidNameReason::Empty);
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(InvalidNameReason::NameTooLong);
+    }
+    for label in name.split('.') {
+        validate_label(label)?;
+    }
+    if !name.contains('.') {
+        return Err(InvalidNameReason::MissingTld);
+    }
+    Ok(name)
+}
+
+/// Normalizes `name` and checks that it ends with `tld`.
+pub fn normalize_with_tld(name: &str, tld: &str) -> Result<String, InvalidNameReason> {
+    let name = normalize(name)?;
+    if tld_of(&name) != tld {
+        return Err(InvalidNameReason::MissingTld);
+    }
+    Ok(name)
+}
+
+/// Normalizes a single label, e.g. a TLD, and checks that it is valid.
+pub fn normalize_label(label: &str) -> Result<String, InvalidNameReason> {
+    let label = fold(label);
+    validate_label(&label)?;
+    Ok(label)
+}
+
+/// Returns `true` if `name` is valid and already normalized.
+pub fn is_normalized(name: &str) -> bool {
+    normalize(name).is_ok_and(|normalized| normalized == name)
+}
+
+/// Returns `true` if `name` has more than one label in front of its TLD,
+/// e.g. `pay.alice.vne` but not `alice.vne`.
+pub fn is_subdomain(name: &str) -> bool {
+    name.split('.').count() > 2
+}
+
+/// Returns the last label of `name`.
+pub fn tld_of(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(_, tld)| tld)
+}
+
+fn validate_label(label: &str) -> Result<(), InvalidNameReason> {
+    let length = label.chars().count();
+    if length == 0 {
+        return Err(InvalidNameReason::EmptyLabel);
+    }
+    if length > MAX_LABEL_LENGTH {
+        return Err(InvalidNameReason::LabelTooLong);
+    }
+    if label.starts_with('-') || label.ends_with('-') {
+        return Err(InvalidNameReason::InvalidHyphen);
+    }
+    // ascii is restricted to `a-z`, `0-9` and `-`, other scripts to letters and digits
+    let valid_character = |c: char| {
+        c == '-'
+            || c.is_ascii_lowercase()
+            || c.is_ascii_digit()
+            || (!c.is_ascii() && c.is_alphanumeric())
+    };
+    if !label.chars().all(valid_character) {
+        return Err(InvalidNameReason::InvalidCharacter);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_folds_case() {
+        assert_eq!(normalize("Alice.VNE"), Ok(String::from("alice.vne")));
+        assert!(is_normalized("alice.vne"));
+        assert!(!is_normalized("Alice.vne"));
+    }
+
+    #[test]
+    fn normalize_applies_nfc() {
+        // "e" followed by a combining acute accent composes to "é"
+        assert_eq!(
+            normalize("cafe\u{301}.vne"),
+            Ok(String::from("caf\u{e9}.vne"))
+        );
+    }
+
+    #[test]
+    fn normalize_rejects_invalid_labels() {
+        assert_eq!(normalize(""), Err(InvalidNameReason::Empty));
+        assert_eq!(normalize(".vne"), Err(InvalidNameReason::EmptyLabel));
+        assert_eq!(normalize("a..vne"), Err(InvalidNameReason::EmptyLabel));
+        assert_eq!(
+            normalize("a b.vne"),
+            Err(InvalidNameReason::InvalidCharacter)
+        );
+        assert_eq!(
+            normalize("a_b.vne"),
+            Err(InvalidNameReason::InvalidCharacter)
+        );
+        assert_eq!(normalize("-ab.vne"), Err(InvalidNameReason::InvalidHyphen));
+        assert_eq!(normalize("ab-.vne"), Err(InvalidNameReason::InvalidHyphen));
+        assert_eq!(normalize("alice"), Err(InvalidNameReason::MissingTld));
+
+        let long_label = "a".repeat(MAX_LABEL_LENGTH + 1) + ".vne";
+        assert_eq!(normalize(&long_label), Err(InvalidNameReason::LabelTooLong));
+    }
+
+    #[test]
+    fn normalize_with_tld_requires_suffix() {
+        assert_eq!(
+            normalize_with_tld("a.vne", DEFAULT_TLD),
+            Ok(String::from("a.vne"))
+        );
+        assert_eq!(
+            normalize_with_tld("a.vne.evil", DEFAULT_TLD),
+            Err(InvalidNameReason::MissingTld)
+        );
+        assert_eq!(
+            normalize_with_tld("vne.com", DEFAULT_TLD),
+            Err(InvalidNameReason::MissingTld)
+        );
+    }
+
+    #[test]
+    fn normalize_label_works() {
+        assert_eq!(normalize_label("DAO"), Ok(String::from("dao")));
+        assert_eq!(
+            normalize_label("d.ao"),
+            Err(InvalidNameReason::InvalidCharacter)
+        );
+        assert_eq!(normalize_label(""), Err(InvalidNameReason::EmptyLabel));
+    }
+
+    #[test]
+    fn tld_of_works() {
+        assert_eq!(tld_of("alice.vne"), "vne");
+        assert_eq!(tld_of("pay.alice.vne"), "vne");
+        assert_eq!(tld_of("vne"), "vne");
+    }
+
+    #[test]
+    fn is_subdomain_works() {
+        assert!(!is_subdomain("alice.vne"));
+        assert!(is_subdomain("pay.alice.vne"));
+    }
+}
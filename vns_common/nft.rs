@@ -0,0 +1,54 @@
+//! The NFT's interface, as called by the registrar.
+
+use alloc::string::String;
+
+use crate::access::{AccessError, AccountId};
+use crate::call::CallError;
+
+/// A token ID.
+pub type TokenId = u64;
+
+/// Why the NFT rejected a call.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum NftError {
+    NotOwner,
+    NotApproved,
+    TokenExists,
+    TokenNotFound,
+    CannotInsert,
+    CannotFetchValue,
+    NotAllowed,
+    DomainNotRegistered,
+    Paused,
+    Access(AccessError),
+    UpgradeFailed,
+    /// A call to another contract failed.
+    Call(CallError),
+}
+
+impl From<AccessError> for NftError {
+    fn from(error: AccessError) -> Self {
+        NftError::Access(error)
+    }
+}
+
+impl From<CallError> for NftError {
+    fn from(error: CallError) -> Self {
+        NftError::Call(error)
+    }
+}
+
+/// The NFT messages other VNS contracts call.
+#[ink::trait_definition]
+pub trait VnsNft {
+    /// Mints token `id` of the registered `domain_name` to `caller`.
+    #[ink(message, selector = 0xCFDD9AA2)]
+    fn mint(
+        &mut self,
+        id: TokenId,
+        domain_name: String,
+        caller: AccountId,
+        token_uri: String,
+    ) -> Result<(), NftError>;
+}
@@ -0,0 +1,172 @@
+//! Price types shared by the price oracle and its callers, and the oracle's interface.
+
+use alloc::string::String;
+
+use crate::access::{AccessError, AccountId};
+use crate::{Balance, Timestamp};
+
+/// A price as quoted by the price oracle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Price {
+    /// The price from the name length, duration and premium names.
+    pub base: u128,
+    /// The temporary premium of a recently released name.
+    pub premium: u128,
+    /// `base` and `premium` together, which is what has to be paid.
+    pub total: u128,
+}
+
+/// A price broken down into its parts, in the native balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct PriceQuote {
+    /// The part charged for the length of the name.
+    pub length: u128,
+    /// The part charged for the registration duration.
+    pub duration: u128,
+    /// Applied to `length` and `duration` together, 10 for premium names and 1 otherwise.
+    pub premium_multiplier: u128,
+    /// The temporary premium of a recently released name.
+    pub premium: u128,
+    /// Taken off by the registrar, e.g. for vouchers. The oracle never quotes one.
+    pub discount: u128,
+    /// What has to be paid.
+    pub total: u128,
+}
+
+/// Why the price oracle could not quote a price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PriceError {
+    /// The price does not fit a `u128`.
+    Overflow,
+    /// No exchange rate has been reported yet.
+    NoExchangeRate,
+    /// The last reported exchange rate is older than the oracle allows.
+    StaleExchangeRate,
+    /// A duration of zero cannot be priced.
+    ZeroDuration,
+    /// The prices left behind by older code are not converted yet, see `migrate`.
+    NotMigrated,
+}
+
+/// Why the price oracle rejected a call.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PriceOracleError {
+    Access(AccessError),
+    ZeroHalfLife,
+    ZeroRate,
+    UpgradeFailed,
+    AlreadyMigrated,
+    /// Prices could not be converted, e.g. without an exchange rate.
+    Price(PriceError),
+}
+
+impl From<AccessError> for PriceOracleError {
+    fn from(error: AccessError) -> Self {
+        PriceOracleError::Access(error)
+    }
+}
+
+impl From<PriceError> for PriceOracleError {
+    fn from(error: PriceError) -> Self {
+        PriceOracleError::Price(error)
+    }
+}
+
+/// The price oracle messages other VNS contracts call.
+#[ink::trait_definition]
+pub trait VnsPriceOracle {
+    /// Quotes registering `name` for `duration`, in the native balance.
+    #[ink(message, selector = 0xFF7B33A5)]
+    fn quote(&self, name: String, duration: Timestamp) -> Result<PriceQuote, PriceError>;
+
+    /// Converts a native `amount` to `token`, `None` if the token has no current rate.
+    #[ink(message, selector = 0xD511F459)]
+    fn convert_to_token(&self, token: AccountId, amount: Balance) -> Option<Balance>;
+
+    #[ink(message, selector = 0xD22B4050)]
+    fn is_premium_name(&self, name: String) -> bool;
+
+    /// Records that `name` was released, which starts its release premium.
+    #[ink(message, selector = 0xBE4449D1)]
+    fn record_release(
+        &mut self,
+        name: String,
+        released_at: Timestamp,
+    ) -> Result<(), PriceOracleError>;
+}
+
+impl Price {
+    /// Returns `None` if the total overflows.
+    pub fn new(base: u128, premium: u128) -> Option<Self> {
+        Some(Self {
+            base,
+            premium,
+            total: base.checked_add(premium)?,
+        })
+    }
+}
+
+impl PriceQuote {
+    /// Computes the total of the parts, without a discount.
+    pub fn new(
+        length: u128,
+        duration: u128,
+        premium_multiplier: u128,
+        premium: u128,
+    ) -> Result<Self, PriceError> {
+        let base = length
+            .checked_add(duration)
+            .and_then(|base| base.checked_mul(premium_multiplier))
+            .ok_or(PriceError::Overflow)?;
+        Ok(Self {
+            length,
+            duration,
+            premium_multiplier,
+            premium,
+            discount: 0,
+            total: base.checked_add(premium).ok_or(PriceError::Overflow)?,
+        })
+    }
+
+    /// The price without the temporary premium or a discount, as charged for renewals.
+    pub fn base(&self) -> u128 {
+        self.length
+            .saturating_add(self.duration)
+            .saturating_mul(self.premium_multiplier)
+    }
+
+    /// Takes `amount` off the total, but never more than is left of it.
+    pub fn apply_discount(&mut self, amount: u128) {
+        let amount = amount.min(self.total);
+        self.discount += amount;
+        self.total -= amount;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_adds_up() {
+        let mut quote = PriceQuote::new(5, 20, 10, 100).unwrap();
+        assert_eq!(quote.base(), 250);
+        assert_eq!(quote.total, 350);
+
+        quote.apply_discount(50);
+        quote.apply_discount(1000);
+        assert_eq!(quote.discount, 350);
+        assert_eq!(quote.total, 0);
+        // renewals still pay the full base price
+        assert_eq!(quote.base(), 250);
+
+        assert_eq!(
+            PriceQuote::new(u128::MAX, 1, 1, 0),
+            Err(PriceError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! Records a domain can be registered with, written by the resolver.
+
+use alloc::string::String;
+
+/// A single record of a domain.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum RecordEntry {
+    /// Stored as the content hash of the domain.
+    ContentHash(String),
+    /// An address, at `index` of the domain's address records.
+    Address { index: u32, address: String },
+    /// A text entry under `key`: `social`, `general` or `address` at `index`, or
+    /// `other`, which has no index.
+    Text {
+        key: String,
+        index: u32,
+        value: String,
+    },
+}
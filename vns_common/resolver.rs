@@ -0,0 +1,95 @@
+//! The resolver's interface, as called by the registrar, registry and NFT.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::access::{AccessError, AccountId};
+use crate::record::RecordEntry;
+use crate::{Hash, Timestamp};
+
+/// Why the resolver rejected a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ResolverError {
+    UndefinedError,
+    DomainNotRegistered,
+    InvalidCaller,
+    DomainNotExpired,
+    RenewTimeExpired,
+    InvalidContentKey,
+    Unauthorized,
+    InvalidDomainName,
+    InvalidLabel,
+    DomainAlreadyRegistered,
+    Paused,
+    Access(AccessError),
+    ReleaseReportFailed,
+    UpgradeFailed,
+    AlreadyMigrated,
+    InvalidContentIndex,
+}
+
+impl From<AccessError> for ResolverError {
+    fn from(error: AccessError) -> Self {
+        ResolverError::Access(error)
+    }
+}
+
+/// The resolver messages other VNS contracts call.
+#[ink::trait_definition]
+pub trait VnsResolver {
+    /// Writes the record of a new domain. Only controllers may call this.
+    // the arguments are the resolver's ABI since before the trait existed
+    #[allow(clippy::too_many_arguments)]
+    #[ink(message, selector = 0xB078450C)]
+    fn set_record(
+        &mut self,
+        label: Hash,
+        domain_name: String,
+        domain_owner: AccountId,
+        duration: Timestamp,
+        secret: [u8; 32],
+        resolver: AccountId,
+        domain_expiry_time: Timestamp,
+    ) -> Result<(), ResolverError>;
+
+    /// Writes several records at once, all or none of them.
+    #[ink(message, selector = 0x3BD7B4E5)]
+    fn set_records(
+        &mut self,
+        domain_name: String,
+        records: Vec<RecordEntry>,
+    ) -> Result<(), ResolverError>;
+
+    /// Extends the expiry of a domain. Only controllers may call this.
+    #[ink(message, selector = 0x3B83A7F0)]
+    fn renew_domain(
+        &mut self,
+        domain_name: String,
+        new_duration: Timestamp,
+    ) -> Result<(), ResolverError>;
+
+    #[ink(message, selector = 0x94194910)]
+    fn register_subdomain(
+        &mut self,
+        parent_domain: String,
+        sub_domain: String,
+    ) -> Result<(), ResolverError>;
+
+    #[ink(message, selector = 0x550C4F17)]
+    fn read_grace_period(&self) -> Timestamp;
+
+    /// The reads of a domain fail with `DomainNotRegistered` if it has no record.
+    #[ink(message, selector = 0x46852A73)]
+    fn read_content_hash(&self, domain_name: String) -> Result<String, ResolverError>;
+
+    #[ink(message, selector = 0x2AE78455)]
+    fn read_domain_owner(&self, domain_name: String) -> Result<AccountId, ResolverError>;
+
+    #[ink(message, selector = 0xF3079BD8)]
+    fn read_domain_expiry_time(&self, domain_name: String) -> Result<Timestamp, ResolverError>;
+
+    /// Returns `true` if `domain_name` has no record.
+    #[ink(message, selector = 0x34EA0F21)]
+    fn check_domain_availablility(&self, domain_name: String) -> bool;
+}
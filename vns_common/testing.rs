@@ -0,0 +1,95 @@
+//! Helpers for the contracts' off-chain unit tests.
+
+use crate::access::AccountId;
+
+/// Makes `account` the caller of the next messages.
+///
+/// The off-chain engine only sets 32 byte accounts, `env().caller()` decodes
+/// the first 20 bytes of them.
+pub fn set_caller(account: AccountId) {
+    let mut caller = [0u8; 32];
+    caller[..20].copy_from_slice(&account);
+    ink::env::test::set_caller::<ink::env::DefaultEnvironment>(caller.into());
+}
+
+#[cfg(feature = "testing")]
+pub use mock::*;
+
+/// Mocked callees for the calls made through [`crate::call`], which the off-chain
+/// engine cannot make.
+#[cfg(feature = "testing")]
+mod mock {
+    use alloc::collections::BTreeMap;
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use ink::env::call::ExecutionInput;
+    use ink::env::ReturnErrorCode;
+    use ink::{LangError, MessageResult};
+    use scale::{Decode, Encode};
+
+    /// The encoded answer of a mocked callee, or why the call failed.
+    pub type Answer = Result<Vec<u8>, ink::env::Error>;
+
+    type Handler = Rc<RefCell<dyn FnMut([u8; 4], &[u8]) -> Answer>>;
+
+    std::thread_local! {
+        static CALLEES: RefCell<BTreeMap<Vec<u8>, Handler>> = RefCell::new(BTreeMap::new());
+    }
+
+    /// Answers the calls to `callee` with `handler`, which gets the selector and the
+    /// encoded arguments of each call. Calls to accounts without a handler fail as
+    /// if there was no contract.
+    pub fn mock_callee<A: Encode>(
+        callee: A,
+        handler: impl FnMut([u8; 4], &[u8]) -> Answer + 'static,
+    ) {
+        let handler: Handler = Rc::new(RefCell::new(handler));
+        CALLEES.with(|callees| callees.borrow_mut().insert(callee.encode(), handler));
+    }
+
+    /// Removes the handler of `callee`, which makes it a plain account again.
+    pub fn unmock_callee<A: Encode>(callee: A) {
+        CALLEES.with(|callees| callees.borrow_mut().remove(&callee.encode()));
+    }
+
+    /// The answer of a message that returned `value`.
+    pub fn answer<T: Encode>(value: T) -> Answer {
+        Ok(MessageResult::Ok(value).encode())
+    }
+
+    /// The answer of a callee that trapped, e.g. because it panicked.
+    pub fn trap() -> Answer {
+        Err(ink::env::Error::ReturnError(ReturnErrorCode::CalleeTrapped))
+    }
+
+    /// The answer of a callee without a message with the called selector.
+    pub fn unknown_message() -> Answer {
+        Ok(MessageResult::<()>::Err(LangError::CouldNotReadInput).encode())
+    }
+
+    /// Decodes the arguments a handler got.
+    pub fn decode_args<T: Decode>(args: &[u8]) -> T {
+        T::decode(&mut &args[..]).expect("arguments of another message")
+    }
+
+    pub(crate) fn invoke<A, Args, R>(
+        callee: &A,
+        input: &ExecutionInput<Args>,
+    ) -> ink::env::Result<MessageResult<R>>
+    where
+        A: Encode,
+        Args: Encode,
+        R: Decode,
+    {
+        let handler = CALLEES.with(|callees| callees.borrow().get(&callee.encode()).cloned());
+        let Some(handler) = handler else {
+            return Err(ink::env::Error::ReturnError(ReturnErrorCode::CodeNotFound));
+        };
+        let input = input.encode();
+        let selector = [input[0], input[1], input[2], input[3]];
+        let answer = (handler.borrow_mut())(selector, &input[4..])?;
+        MessageResult::<R>::decode(&mut &answer[..]).map_err(ink::env::Error::Decode)
+    }
+}
@@ -0,0 +1,16 @@
+//! The errors of the PSP22 tokens the registrar accepts as payment.
+
+use alloc::string::String;
+
+/// Why a PSP22 token rejected a call, as defined by the PSP22 standard.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum PSP22Error {
+    /// An error of the token's own.
+    Custom(String),
+    InsufficientBalance,
+    InsufficientAllowance,
+    ZeroRecipientAddress,
+    ZeroSenderAddress,
+    SafeTransferCheckFailed(String),
+}